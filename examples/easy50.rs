@@ -1,6 +1,11 @@
+use std::fs::File;
+use std::io::BufReader;
+
 fn main() {
-    let data = std::fs::read_to_string("data/easy50.txt").unwrap();
-    let puzzles = data.lines().collect::<Vec<_>>();
+    let file = File::open("data/easy50.txt").unwrap();
+    let puzzles = suji::PuzzleReader::new(BufReader::new(file))
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
 
     let mut sudoku = suji::Sudoku::new(suji::BackendType::BitfieldGrid);
     let mut solver = suji::WaveFunctionCollapseSolver::new();
@@ -8,7 +13,7 @@ fn main() {
 
     let now = std::time::Instant::now();
     for puzzle in puzzles {
-        sudoku.load_from_str(puzzle, '0');
+        sudoku.load_grid(&puzzle.grid);
         sudoku.solve(&mut solver);
     }
     println!(
@@ -1,5 +1,4 @@
 use std::time::Instant;
-use suji;
 
 const EASY_TEST: &str = "003020600900305001001806400008102900700000008006708200002609500800203009005010300";
 const HARD_TEST: &str = "900050200400000780000087600000360000005902400000018000004820000051000002006090007";
@@ -0,0 +1,125 @@
+use crate::error::Error;
+use crate::grid::Grid;
+use crate::logic::{combinations, LogicState, Technique};
+
+// The smallest sets of cells that, once their solution values are revealed, let a set of
+// logical techniques finish the puzzle
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Backdoors {
+    pub size: usize,
+    pub sets: Vec<Vec<(usize, usize)>>,
+}
+
+impl Backdoors {
+    // Every cell that is part of at least one backdoor
+    pub fn cells(&self) -> Vec<(usize, usize)> {
+        let mut cells = self.sets.concat();
+        cells.sort_unstable();
+        cells.dedup();
+        cells
+    }
+}
+
+impl Grid {
+    // None if no backdoor of at most max_size cells exists. Size 0 means the techniques solve the
+    // puzzle on their own.
+    pub fn backdoors(&self, techniques: &[Technique], max_size: usize) -> Result<Option<Backdoors>, Error> {
+        let solution = self.try_unique_solution()?;
+
+        // revealing a cell the techniques find anyway never helps, so only the cells left open
+        // after applying them to the puzzle have to be tried
+        let mut base = LogicState::from_grid(self);
+        base.solve(techniques);
+        let open = (0..81).filter(|&cell| base.values[cell] == 0).collect::<Vec<_>>();
+
+        for size in 0..=max_size.min(open.len()) {
+            let mut sets = Vec::new();
+            for cells in combinations(&open, size) {
+                let mut state = base;
+                for &cell in &cells {
+                    state.place(cell, solution.cells()[cell]);
+                }
+                if state.solve(techniques) {
+                    sets.push(cells.iter().map(|&cell| (cell / 9, cell % 9)).collect());
+                }
+            }
+            if !sets.is_empty() {
+                return Ok(Some(Backdoors {
+                    size,
+                    sets,
+                }));
+            }
+        }
+        Ok(None)
+    }
+
+    pub fn backdoor_size(&self, techniques: &[Technique], max_size: usize) -> Result<Option<usize>, Error> {
+        Ok(self.backdoors(techniques, max_size)?.map(|backdoors| backdoors.size))
+    }
+
+    // Backdoors for solving with naked and hidden singles only
+    pub fn singles_backdoors(&self, max_size: usize) -> Result<Option<Backdoors>, Error> {
+        self.backdoors(&Technique::SINGLES, max_size)
+    }
+
+    // The cells that hold the same value in every solution, all other cells are 0. A puzzle with
+    // a unique solution is its own solution's backbone.
+    pub fn backbone(&self) -> Result<Grid, Error> {
+        let first = self.first_solution().ok_or(Error::NoSolution)?;
+        let mut backbone = *first.cells();
+        for cell in 0..81 {
+            let value = backbone[cell];
+            if value == 0 || self.cells()[cell] != 0 {
+                continue;
+            }
+            // any solution with another value here rules out every cell where it differs
+            for other in (1..=9).filter(|&other| other != value) {
+                let mut grid = *self;
+                grid.set(cell / 9, cell % 9, other);
+                if let Some(solution) = grid.first_solution() {
+                    for (fixed, &found) in backbone.iter_mut().zip(solution.cells()) {
+                        if *fixed != found {
+                            *fixed = 0;
+                        }
+                    }
+                    break;
+                }
+            }
+        }
+        Ok(Grid::from_cells(backbone))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PUZZLE: &str = "000000907000420180000705026100904000050000040000507009920108000034059000507000000";
+
+    #[test]
+    fn hidden_singles_alone_do_not_place_naked_singles() {
+        let grid = Grid::parse(PUZZLE, '0').unwrap();
+        let hidden = grid.backdoors(&[Technique::HiddenSingle], 2).unwrap().unwrap();
+        let singles = grid.backdoors(&Technique::SINGLES, 2).unwrap().unwrap();
+        assert_eq!(singles.size, 0);
+        assert_eq!(hidden.size, 1);
+        assert_ne!(hidden, singles);
+    }
+
+    #[test]
+    fn subsets_are_built_lazily_in_order() {
+        let subsets = combinations(&[1, 2, 3, 4], 2).collect::<Vec<_>>();
+        assert_eq!(subsets, [[1, 2], [1, 3], [1, 4], [2, 3], [2, 4], [3, 4]]);
+        assert_eq!(combinations(&[1, 2], 0).count(), 1);
+        assert_eq!(combinations(&[1, 2], 3).count(), 0);
+        let open = (0..81).collect::<Vec<usize>>();
+        assert_eq!(combinations(&open, 40).next(), Some((0..40).collect()));
+    }
+
+    #[test]
+    fn no_techniques_place_nothing() {
+        let grid = Grid::parse(PUZZLE, '0').unwrap();
+        assert_eq!(grid.backdoor_size(&[], 1).unwrap(), None);
+    }
+}
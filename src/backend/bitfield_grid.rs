@@ -1,7 +1,9 @@
 use super::Backend;
+use crate::unit::{Conflict, Unit};
 
 const CELL_MASK_LEN: usize = 9;
 const CELL_MASK: u128 = 0b111111111;
+const ALL_CANDIDATES: u16 = 0b111111111;
 
 pub const BOXES: [[usize; 9]; 9] = [
     [0, 0, 0, 1, 1, 1, 2, 2, 2],
@@ -36,8 +38,14 @@ pub struct BitfieldGrid {
     cols: [u128; 9],
     boxes: [u128; 9],
 
-    valid: bool,
+    // One bit per unit that currently contains a duplicate: rows 0-8, cols 9-17, boxes 18-26
+    invalid_units: u32,
     empty_cells: Vec<(usize, usize)>,
+
+    // Candidates removed by set_candidates, kept apart from the ones ruled out by placed values so
+    // set and unset never have to touch them. The candidates of a cell are its possible values
+    // without these.
+    eliminated: [u16; 81],
 }
 
 impl BitfieldGrid {
@@ -47,13 +55,40 @@ impl BitfieldGrid {
             cols: [0; 9],
             boxes: [0; 9],
 
-            valid: true,
+            invalid_units: 0,
             empty_cells: (0..81).map(Self::index_to_coords).collect(),
+
+            eliminated: [0; 81],
+        }
+    }
+
+    // Values already held by a peer of the cell
+    #[inline]
+    fn peer_values(&self, row: usize, col: usize) -> u16 {
+        let mut used = self.rows[row] | self.cols[col] | self.boxes[BOXES[row][col]];
+        let mut values = 0;
+        for _ in 0..9 {
+            values |= used & CELL_MASK;
+            used >>= CELL_MASK_LEN;
         }
+        values as u16
+    }
+
+    // Re-checks the three units of a cell, the grid stays invalid as long as any unit is invalid
+    fn verify_cell(&mut self, row: usize, col: usize) {
+        let box_idx = BOXES[row][col];
+        self.update_unit(row, self.verify_row(row));
+        self.update_unit(9 + col, self.verify_col(col));
+        self.update_unit(18 + box_idx, self.verify_box(box_idx));
     }
 
-    fn verify_cell(&self, row: usize, col: usize) -> bool {
-        self.verify_row(row) && self.verify_col(col) && self.verify_box(BOXES[row][col])
+    #[inline]
+    fn update_unit(&mut self, unit: usize, valid: bool) {
+        if valid {
+            self.invalid_units &= !(1 << unit);
+        } else {
+            self.invalid_units |= 1 << unit;
+        }
     }
 
     fn verify_row(&self, row: usize) -> bool {
@@ -92,13 +127,20 @@ impl BitfieldGrid {
     }
 }
 
+impl Default for BitfieldGrid {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Backend for BitfieldGrid {
     fn reset(&mut self) {
         self.rows = [0; 9];
         self.cols = [0; 9];
         self.boxes = [0; 9];
-        self.valid = true;
+        self.invalid_units = 0;
         self.empty_cells = (0..81).map(Self::index_to_coords).collect();
+        self.eliminated = [0; 81];
     }
 
     #[inline]
@@ -122,7 +164,7 @@ impl Backend for BitfieldGrid {
         self.cols[col] |= mask << (row * CELL_MASK_LEN);
         self.boxes[BOXES[row][col]] |= mask << (IN_BOXES_IDX[row][col] * CELL_MASK_LEN);
 
-        self.valid = self.verify_cell(row, col);
+        self.verify_cell(row, col);
         self.empty_cells.retain(|&(r, c)| r != row || c != col);
     }
 
@@ -138,7 +180,7 @@ impl Backend for BitfieldGrid {
         self.cols[col] &= !(mask << (row * CELL_MASK_LEN));
         self.boxes[BOXES[row][col]] &= !(mask << (IN_BOXES_IDX[row][col] * CELL_MASK_LEN));
 
-        self.valid = self.verify_cell(row, col);
+        self.verify_cell(row, col);
         self.empty_cells.push((row, col));
     }
 
@@ -150,10 +192,12 @@ impl Backend for BitfieldGrid {
         self.cols[col] |= mask << (row * CELL_MASK_LEN);
         self.boxes[BOXES[row][col]] |= mask << (IN_BOXES_IDX[row][col] * CELL_MASK_LEN);
 
-        self.valid = self.verify_cell(row, col);
+        self.verify_cell(row, col);
         self.empty_cells.retain(|&(r, c)| r != row || c != col);
     }
 
+    // Skips verify_cell, so is_valid and get_conflicts do not see a conflict this placement
+    // creates until the cell's units are set or unset again through a checked method
     #[inline]
     fn set_not_zero_unckecked(&mut self, row: usize, col: usize, value: u8) {
         let mask = 1 << (value - 1);
@@ -165,6 +209,27 @@ impl Backend for BitfieldGrid {
         self.empty_cells.retain(|&(r, c)| r != row || c != col);
     }
 
+    #[inline]
+    fn get_candidates(&self, row: usize, col: usize) -> u16 {
+        match self.get(row, col) {
+            0 => self.get_possible_values(row, col) & !self.eliminated[row * 9 + col],
+            value => 1 << (value - 1),
+        }
+    }
+
+    // Only the possible values of the cell can be eliminated or given back, values ruled out by
+    // a peer come back on their own once the peer is unset
+    #[inline]
+    fn set_candidates(&mut self, row: usize, col: usize, candidates: u16) {
+        let possible = self.get_possible_values(row, col);
+        let eliminated = &mut self.eliminated[row * 9 + col];
+        *eliminated = *eliminated & !possible | possible & !candidates;
+    }
+
+    fn reset_candidates(&mut self) {
+        self.eliminated = [0; 81];
+    }
+
     #[inline]
     fn get_empty_cells(&self) -> &Vec<(usize, usize)> {
         &self.empty_cells
@@ -172,7 +237,28 @@ impl Backend for BitfieldGrid {
 
     #[inline]
     fn is_valid(&self) -> bool {
-        self.valid
+        self.invalid_units == 0
+    }
+
+    fn get_conflicts(&self) -> Vec<Conflict> {
+        if self.invalid_units == 0 {
+            return Vec::new();
+        }
+        Unit::all()
+            .enumerate()
+            .filter(|(i, _)| self.invalid_units & 1 << i != 0)
+            .flat_map(|(_, unit)| self.get_unit_conflicts(unit))
+            .collect()
+    }
+
+    // Same as checking every value with is_possible_value, a cell in an invalid unit has none
+    #[inline]
+    fn get_possible_values(&self, row: usize, col: usize) -> u16 {
+        let units = 1 << row | 1 << (9 + col) | 1 << (18 + BOXES[row][col]);
+        if self.get(row, col) != 0 || self.invalid_units & units != 0 {
+            return 0;
+        }
+        !self.peer_values(row, col) & ALL_CANDIDATES
     }
 
     #[inline]
@@ -1,5 +1,8 @@
 mod bitfield_grid;
 
+use crate::error::Error;
+use crate::grid::Grid;
+use crate::unit::{Conflict, Unit};
 pub use bitfield_grid::BitfieldGrid;
 use std::fmt::Debug;
 
@@ -16,10 +19,15 @@ pub trait Backend: Debug {
     fn set(&mut self, row: usize, col: usize, value: u8);
     fn unset(&mut self, row: usize, col: usize);
 
-    // Used for optimization in solvers
+    // Used for optimization in solvers, the unchecked variant does not update is_valid
     fn set_not_zero(&mut self, row: usize, col: usize, value: u8);
     fn set_not_zero_unckecked(&mut self, row: usize, col: usize, value: u8);
 
+    // The possible values of a cell minus the ones the caller eliminated, eliminations survive
+    // placing and removing values on peers. A placed value keeps itself as its only candidate.
+    fn get_candidates(&self, row: usize, col: usize) -> u16;
+    fn set_candidates(&mut self, row: usize, col: usize, candidates: u16);
+
     fn get_empty_cells(&self) -> &Vec<(usize, usize)>;
     fn is_valid(&self) -> bool;
 
@@ -36,27 +44,69 @@ pub trait Backend: Debug {
         possible_values
     }
 
+    fn eliminate_candidate(&mut self, row: usize, col: usize, value: u8) {
+        let candidates = self.get_candidates(row, col);
+        self.set_candidates(row, col, candidates & !(1 << (value - 1)));
+    }
+
+    // Throws away all eliminations and recomputes the candidates from the placed values
+    fn reset_candidates(&mut self) {
+        for i in 0..81 {
+            let (row, col) = (i / 9, i % 9);
+            let candidates = match self.get(row, col) {
+                0 => self.get_possible_values(row, col),
+                value => 1 << (value - 1),
+            };
+            self.set_candidates(row, col, candidates);
+        }
+    }
+
+    // Every pair of equal values sharing a unit, a pair sharing two units is reported for both
+    fn get_conflicts(&self) -> Vec<Conflict> {
+        Unit::all().flat_map(|unit| self.get_unit_conflicts(unit)).collect()
+    }
+
+    fn get_unit_conflicts(&self, unit: Unit) -> Vec<Conflict> {
+        let cells = unit.cells();
+        let mut conflicts = Vec::new();
+        for (i, &first) in cells.iter().enumerate() {
+            let value = self.get(first.0, first.1);
+            if value == 0 {
+                continue;
+            }
+            for &second in &cells[i + 1..] {
+                if self.get(second.0, second.1) == value {
+                    conflicts.push(Conflict {
+                        first,
+                        second,
+                        unit,
+                        value,
+                    });
+                }
+            }
+        }
+        conflicts
+    }
+
     fn load_from_str(&mut self, input: &str, null_chr: char) {
-        if input.len() != 81 {
+        if let Err(err) = self.try_load_from_str(input, null_chr) {
             println!("{}", input);
-            panic!("Input string must be 81 characters long");
+            panic!("{}", err);
         }
+    }
 
-        let digit_grid = input
-            .chars()
-            .map(|c| {
-                if c == null_chr {
-                    return 0;
-                }
-                c.to_digit(10).expect("Invalid character in board string.") as u8
-            })
-            .collect::<Vec<u8>>();
+    fn try_load_from_str(&mut self, input: &str, null_chr: char) -> Result<(), Error> {
+        let grid = Grid::parse(input, null_chr)?;
+        self.load_grid(&grid);
+        Ok(())
+    }
 
+    fn load_grid(&mut self, grid: &Grid) {
         self.reset();
 
         for i in 0..9 {
             for j in 0..9 {
-                let value = digit_grid[i * 9 + j];
+                let value = grid.get(i, j);
                 if value != 0 {
                     self.set(i, j, value);
                 }
@@ -64,6 +114,16 @@ pub trait Backend: Debug {
         }
     }
 
+    fn to_grid(&self) -> Grid {
+        let mut grid = Grid::new();
+        for i in 0..9 {
+            for j in 0..9 {
+                grid.set(i, j, self.get(i, j));
+            }
+        }
+        grid
+    }
+
     fn to_string(&self, null_chr: char) -> String {
         let mut output = String::with_capacity(81);
         for i in 0..9 {
@@ -0,0 +1,134 @@
+use crate::backend::BackendType;
+use crate::error::Error;
+use crate::grid::Grid;
+use crate::reader::Puzzle;
+use crate::solver::{Solver, SolverStats, WaveFunctionCollapseSolver};
+use crate::Sudoku;
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+
+// Anything a batch can load into a sudoku before solving it
+pub trait BatchInput {
+    fn load_into(&self, sudoku: &mut Sudoku) -> Result<(), Error>;
+}
+
+impl BatchInput for &str {
+    fn load_into(&self, sudoku: &mut Sudoku) -> Result<(), Error> {
+        sudoku.try_load_from_str(self, '0')
+    }
+}
+
+impl BatchInput for String {
+    fn load_into(&self, sudoku: &mut Sudoku) -> Result<(), Error> {
+        sudoku.try_load_from_str(self, '0')
+    }
+}
+
+impl BatchInput for Grid {
+    fn load_into(&self, sudoku: &mut Sudoku) -> Result<(), Error> {
+        sudoku.load_grid(self);
+        Ok(())
+    }
+}
+
+impl BatchInput for Puzzle {
+    fn load_into(&self, sudoku: &mut Sudoku) -> Result<(), Error> {
+        sudoku.load_grid(&self.grid);
+        Ok(())
+    }
+}
+
+// Solves every puzzle in order, reusing a single sudoku and solver for the whole batch
+pub fn solve_batch<I>(puzzles: I) -> impl Iterator<Item = Result<Grid, Error>>
+where
+    I: IntoIterator,
+    I::Item: BatchInput,
+{
+    solve_batch_with(puzzles, WaveFunctionCollapseSolver::new())
+}
+
+pub fn solve_batch_with<I, S>(puzzles: I, solver: S) -> impl Iterator<Item = Result<Grid, Error>>
+where
+    I: IntoIterator,
+    I::Item: BatchInput,
+    S: Solver,
+{
+    solve_batch_with_stats(puzzles, solver).map(|(result, _)| result)
+}
+
+// Every result comes with the statistics of the solver for that puzzle, puzzles that could not be
+// loaded report default statistics
+pub fn solve_batch_with_stats<I, S>(
+    puzzles: I,
+    mut solver: S,
+) -> impl Iterator<Item = (Result<Grid, Error>, SolverStats)>
+where
+    I: IntoIterator,
+    I::Item: BatchInput,
+    S: Solver,
+{
+    let mut sudoku = Sudoku::new(BackendType::BitfieldGrid);
+    puzzles
+        .into_iter()
+        .map(move |puzzle| solve_one(&mut sudoku, &mut solver, &puzzle))
+}
+
+// Parallel version of solve_batch, the output keeps the order of the input.
+// Every rayon worker gets its own sudoku and solver which are reused between puzzles
+#[cfg(feature = "rayon")]
+pub fn par_solve_batch<I>(puzzles: I) -> impl IndexedParallelIterator<Item = Result<Grid, Error>>
+where
+    I: IntoParallelIterator,
+    I::Iter: IndexedParallelIterator,
+    I::Item: BatchInput,
+{
+    par_solve_batch_with(puzzles, WaveFunctionCollapseSolver::new)
+}
+
+#[cfg(feature = "rayon")]
+pub fn par_solve_batch_with<I, S, F>(
+    puzzles: I,
+    new_solver: F,
+) -> impl IndexedParallelIterator<Item = Result<Grid, Error>>
+where
+    I: IntoParallelIterator,
+    I::Iter: IndexedParallelIterator,
+    I::Item: BatchInput,
+    S: Solver,
+    F: Fn() -> S + Sync + Send,
+{
+    par_solve_batch_with_stats(puzzles, new_solver).map(|(result, _)| result)
+}
+
+#[cfg(feature = "rayon")]
+pub fn par_solve_batch_with_stats<I, S, F>(
+    puzzles: I,
+    new_solver: F,
+) -> impl IndexedParallelIterator<Item = (Result<Grid, Error>, SolverStats)>
+where
+    I: IntoParallelIterator,
+    I::Iter: IndexedParallelIterator,
+    I::Item: BatchInput,
+    S: Solver,
+    F: Fn() -> S + Sync + Send,
+{
+    puzzles.into_par_iter().map_init(
+        move || (Sudoku::new(BackendType::BitfieldGrid), new_solver()),
+        |(sudoku, solver), puzzle| solve_one(sudoku, solver, &puzzle),
+    )
+}
+
+fn solve_one(
+    sudoku: &mut Sudoku,
+    solver: &mut dyn Solver,
+    puzzle: &impl BatchInput,
+) -> (Result<Grid, Error>, SolverStats) {
+    if let Err(err) = puzzle.load_into(sudoku) {
+        return (Err(err), SolverStats::default());
+    }
+    if !sudoku.is_valid() {
+        return (Err(Error::NoSolution), SolverStats::default());
+    }
+    let solution = sudoku.solve(solver).ok_or(Error::NoSolution);
+    (solution.and_then(|solution| solution.parse()), solver.stats())
+}
@@ -0,0 +1,823 @@
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Read, Write};
+use std::process::ExitCode;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+use suji::{
+    AnnealingSolver, BinaryReader, BinaryWriter, BruteForceSolver, CellSelection, Deduplicator, Error, Generator, Grid,
+    Heuristics, PuzzleReader, RecordKind, SatSolver, Solver, SolverStats, Sudoku, Symmetry, Technique, ValueOrder,
+    WaveFunctionCollapseSolver,
+};
+
+const USAGE: &str = "usage: suji <command> [options] [files...]
+
+Puzzles are read from the given files, or from stdin if there are none or a file is '-'.
+
+commands:
+    solve       print the solution of every puzzle
+    count       print the number of solutions of every puzzle
+    validate    print the puzzles that have exactly one solution, report the others
+    rate        print every puzzle with the hardest technique it needs and the number of steps
+    generate    print new puzzles with a unique solution
+    convert     read puzzles in one format and write them in another
+    canonical   print the minlex form of every puzzle
+    dedup       print every puzzle that is not isomorphic to an earlier one
+    minimal     print the minimal puzzles, report the redundant clues of the others
+    minimize    remove clues from every puzzle until it is minimal, --symmetric keeps
+                the 180 degree symmetry of the clues
+    symmetry    print the clue symmetries, digit symmetries and automorphism count of every
+                puzzle, fails if a puzzle has no symmetric clue layout
+    backdoor    print the singles backdoor size of every puzzle and the cells of its smallest
+                backdoors, fails if there is none of at most --depth cells
+    backbone    print the cells that hold the same digit in every solution and how many there
+                are, fails if a puzzle has no solution
+    cnf         print the DIMACS CNF encoding of every puzzle
+    model       read DIMACS models written by a SAT solver and print the solutions
+    bench       time the solver on all puzzles
+    play        play the first puzzle of the input, a saved game or a new puzzle in the terminal
+
+options:
+    -s, --solver <wfc|brute|sat|anneal>
+                                    solver used by solve and bench (default wfc)
+        --cell <mrv|peers|random>   how the wfc solver picks the cell to branch on (default mrv)
+        --values <ascending|lcv|random>
+                                    order in which the wfc solver tries values (default ascending)
+        --propagate <stages>        comma separated wfc propagation stages out of singles, hidden,
+                                    pointing, claiming, pairs, triples and xwing, or none
+                                    (default hidden)
+    -n, --null <char>               character for empty cells in line output (default 0)
+    -l, --limit <n>                 count stops after n solutions (default 1000)
+    -d, --depth <n>                 largest backdoor size searched for (default 3)
+    -c, --count <n>                 number of puzzles to generate (default 1)
+        --seed <n>                  seed for generate and the random choices of the solvers
+                                    (default: current time)
+        --symmetric                 generated and minimized puzzles keep 180 degree symmetry
+    -f, --from <line|grid|binary>   input format (default line)
+    -t, --to <line|grid|binary>     output format of generate and convert (default line)
+    -o, --output <file>             where play saves the game (default: the resumed file or suji.save)
+    -h, --help                      print this help
+
+exit codes: 0 success, 1 a puzzle was unsolvable or invalid, 2 bad usage or unreadable input";
+
+// Exit codes
+const FAILED: u8 = 1;
+const BAD_INPUT: u8 = 2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Format {
+    Line,
+    Grid,
+    Binary,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SolverKind {
+    WaveFunctionCollapse,
+    BruteForce,
+    Sat,
+    Annealing,
+}
+
+#[derive(Debug)]
+struct Options {
+    command: String,
+    files: Vec<String>,
+    solver: SolverKind,
+    heuristics: Heuristics,
+    null_chr: char,
+    limit: usize,
+    depth: usize,
+    count: usize,
+    seed: Option<u64>,
+    symmetric: bool,
+    from: Format,
+    to: Format,
+    output: Option<String>,
+}
+
+fn parse_format(value: &str) -> Result<Format, String> {
+    match value {
+        "line" => Ok(Format::Line),
+        "grid" => Ok(Format::Grid),
+        "binary" => Ok(Format::Binary),
+        _ => Err(format!("unknown format '{}'", value)),
+    }
+}
+
+// Sets the propagation stages of the heuristics from a list like `singles,hidden,pairs`
+fn parse_stages(value: &str, heuristics: &mut Heuristics) -> Result<(), String> {
+    heuristics.singles = false;
+    heuristics.hidden_singles = false;
+    heuristics.techniques.clear();
+    for stage in value.split(',').filter(|stage| *stage != "none") {
+        let techniques: &[Technique] = match stage {
+            "singles" => {
+                heuristics.singles = true;
+                &[]
+            }
+            "hidden" => {
+                heuristics.hidden_singles = true;
+                &[]
+            }
+            "pointing" => &[Technique::Pointing],
+            "claiming" => &[Technique::Claiming],
+            "pairs" => &[Technique::NakedPair, Technique::HiddenPair],
+            "triples" => &[Technique::NakedTriple, Technique::HiddenTriple],
+            "xwing" => &[Technique::XWing],
+            _ => return Err(format!("unknown propagation stage '{}'", stage)),
+        };
+        heuristics.techniques.extend_from_slice(techniques);
+    }
+    Ok(())
+}
+
+fn parse_number<T: std::str::FromStr>(option: &str, value: &str) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("invalid number '{}' for {}", value, option))
+}
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Options, String> {
+    let mut options = Options {
+        command: String::new(),
+        files: Vec::new(),
+        solver: SolverKind::WaveFunctionCollapse,
+        heuristics: Heuristics::default(),
+        null_chr: '0',
+        limit: 1000,
+        depth: 3,
+        count: 1,
+        seed: None,
+        symmetric: false,
+        from: Format::Line,
+        to: Format::Line,
+        output: None,
+    };
+
+    while let Some(arg) = args.next() {
+        if !arg.starts_with('-') || arg == "-" {
+            if options.command.is_empty() {
+                options.command = arg;
+            } else {
+                options.files.push(arg);
+            }
+            continue;
+        }
+        if arg == "--symmetric" {
+            options.symmetric = true;
+            continue;
+        }
+        if arg == "-h" || arg == "--help" {
+            options.command = "help".to_string();
+            continue;
+        }
+
+        let value = args.next().ok_or(format!("missing value for {}", arg))?;
+        match arg.as_str() {
+            "-s" | "--solver" => {
+                options.solver = match value.as_str() {
+                    "wfc" => SolverKind::WaveFunctionCollapse,
+                    "brute" => SolverKind::BruteForce,
+                    "sat" => SolverKind::Sat,
+                    "anneal" => SolverKind::Annealing,
+                    _ => return Err(format!("unknown solver '{}'", value)),
+                }
+            }
+            "--cell" => {
+                options.heuristics.cell_selection = match value.as_str() {
+                    "mrv" => CellSelection::MinimumRemainingValues,
+                    "peers" => CellSelection::MostConstrainedPeers,
+                    "random" => CellSelection::RandomTieBreak,
+                    _ => return Err(format!("unknown cell selection '{}'", value)),
+                }
+            }
+            "--values" => {
+                options.heuristics.value_order = match value.as_str() {
+                    "ascending" => ValueOrder::Ascending,
+                    "lcv" => ValueOrder::LeastConstraining,
+                    "random" => ValueOrder::Random,
+                    _ => return Err(format!("unknown value order '{}'", value)),
+                }
+            }
+            "--propagate" => parse_stages(&value, &mut options.heuristics)?,
+            "-n" | "--null" => {
+                let mut chars = value.chars();
+                options.null_chr = match (chars.next(), chars.next()) {
+                    (Some(chr), None) => chr,
+                    _ => return Err(format!("null character must be a single character, got '{}'", value)),
+                }
+            }
+            "-l" | "--limit" => options.limit = parse_number(&arg, &value)?,
+            "-d" | "--depth" => options.depth = parse_number(&arg, &value)?,
+            "-c" | "--count" => options.count = parse_number(&arg, &value)?,
+            "--seed" => options.seed = Some(parse_number(&arg, &value)?),
+            "-f" | "--from" => options.from = parse_format(&value)?,
+            "-t" | "--to" => options.to = parse_format(&value)?,
+            "-o" | "--output" => options.output = Some(value),
+            _ => return Err(format!("unknown option '{}'", arg)),
+        }
+    }
+    Ok(options)
+}
+
+fn new_solver(options: &Options) -> Box<dyn Solver> {
+    match options.solver {
+        SolverKind::WaveFunctionCollapse => Box::new(WaveFunctionCollapseSolver::with_heuristics(heuristics(options))),
+        SolverKind::BruteForce => Box::new(BruteForceSolver::new()),
+        SolverKind::Sat => Box::new(SatSolver::new()),
+        SolverKind::Annealing => Box::new(AnnealingSolver::new(seed(options))),
+    }
+}
+
+// A puzzle together with where it came from, used in error messages
+struct Input {
+    source: String,
+    index: usize,
+    grid: Grid,
+}
+
+// Reads all puzzles of all inputs, unreadable records are reported and skipped
+struct Inputs {
+    puzzles: Box<dyn Iterator<Item = (String, usize, Result<Grid, Error>)>>,
+    failed: bool,
+}
+
+impl Inputs {
+    fn open(options: &Options) -> Result<Self, String> {
+        let mut files = options.files.clone();
+        if files.is_empty() {
+            files.push("-".to_string());
+        }
+
+        let mut puzzles: Box<dyn Iterator<Item = (String, usize, Result<Grid, Error>)>> = Box::new(std::iter::empty());
+        for file in files {
+            let reader: Box<dyn BufRead> = if file == "-" {
+                Box::new(BufReader::new(io::stdin()))
+            } else {
+                let opened = File::open(&file).map_err(|err| format!("{}: {}", file, err))?;
+                Box::new(BufReader::new(opened))
+            };
+            let name = if file == "-" { "stdin".to_string() } else { file };
+            let grids = read_grids(reader, options.from).map_err(|err| format!("{}: {}", name, err))?;
+            puzzles = Box::new(
+                puzzles.chain(
+                    grids
+                        .enumerate()
+                        .map(move |(index, grid)| (name.clone(), index + 1, grid)),
+                ),
+            );
+        }
+        Ok(Self {
+            puzzles,
+            failed: false,
+        })
+    }
+}
+
+impl Iterator for Inputs {
+    type Item = Input;
+
+    fn next(&mut self) -> Option<Input> {
+        loop {
+            match self.puzzles.next()? {
+                (source, index, Ok(grid)) => {
+                    return Some(Input {
+                        source,
+                        index,
+                        grid,
+                    })
+                }
+                (source, _, Err(err)) => {
+                    eprintln!("{}: {}", source, err);
+                    self.failed = true;
+                }
+            }
+        }
+    }
+}
+
+fn read_grids(
+    mut reader: Box<dyn BufRead>,
+    format: Format,
+) -> Result<Box<dyn Iterator<Item = Result<Grid, Error>>>, Error> {
+    match format {
+        Format::Line => Ok(Box::new(
+            PuzzleReader::new(reader).map(|puzzle| puzzle.map(|puzzle| puzzle.grid)),
+        )),
+        Format::Binary => Ok(Box::new(BinaryReader::new(reader)?)),
+        Format::Grid => {
+            // grids span several lines and are separated by blank lines
+            let mut text = String::new();
+            reader.read_to_string(&mut text)?;
+            let mut blocks = Vec::new();
+            let mut block = String::new();
+            for line in text.lines().chain(std::iter::once("")) {
+                if !line.trim().is_empty() {
+                    block.push_str(line);
+                    block.push('\n');
+                } else if !block.is_empty() {
+                    blocks.push(block.parse::<Grid>());
+                    block.clear();
+                }
+            }
+            Ok(Box::new(blocks.into_iter()))
+        }
+    }
+}
+
+// Writes grids in one of the output formats
+enum Output<W: Write> {
+    Text(W, Format, char),
+    Binary(BinaryWriter<W>),
+}
+
+impl<W: Write> Output<W> {
+    fn new(writer: W, format: Format, null_chr: char) -> io::Result<Self> {
+        match format {
+            Format::Binary => Ok(Output::Binary(BinaryWriter::new(writer, RecordKind::Puzzle)?)),
+            _ => Ok(Output::Text(writer, format, null_chr)),
+        }
+    }
+
+    fn write(&mut self, grid: &Grid) -> Result<(), Error> {
+        match self {
+            Output::Text(writer, Format::Grid, _) => writeln!(writer, "{}\n", Sudoku::from(*grid))?,
+            Output::Text(writer, _, null_chr) => writeln!(writer, "{}", grid.to_string_with(*null_chr))?,
+            Output::Binary(writer) => writer.write(grid)?,
+        }
+        Ok(())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Output::Text(writer, _, _) => writer.flush(),
+            Output::Binary(writer) => writer.flush(),
+        }
+    }
+}
+
+fn solve(options: &Options, inputs: &mut Inputs, out: &mut impl Write) -> Result<bool, Error> {
+    let mut solver = new_solver(options);
+    let mut ok = true;
+    for input in inputs {
+        let sudoku = Sudoku::from(input.grid);
+        match sudoku.is_valid().then(|| solver.solve(&sudoku)).flatten() {
+            Some(solution) => writeln!(out, "{}", solution)?,
+            None => {
+                eprintln!("{}: puzzle {}: no solution", input.source, input.index);
+                ok = false;
+            }
+        }
+    }
+    Ok(ok)
+}
+
+fn count(options: &Options, inputs: &mut Inputs, out: &mut impl Write) -> Result<bool, Error> {
+    for input in inputs {
+        writeln!(out, "{}", input.grid.count_solutions(options.limit))?;
+    }
+    Ok(true)
+}
+
+fn validate(options: &Options, inputs: &mut Inputs, out: &mut impl Write) -> Result<bool, Error> {
+    let mut ok = true;
+    for input in inputs {
+        match input.grid.try_unique_solution() {
+            Ok(_) => writeln!(out, "{}", input.grid.to_string_with(options.null_chr))?,
+            Err(err) => {
+                eprintln!("{}: puzzle {}: {}", input.source, input.index, err);
+                ok = false;
+            }
+        }
+    }
+    Ok(ok)
+}
+
+fn rate(options: &Options, inputs: &mut Inputs, out: &mut impl Write) -> Result<bool, Error> {
+    for input in inputs {
+        let rating = Sudoku::from(input.grid).rate();
+        writeln!(
+            out,
+            "{}\t{}\t{}",
+            input.grid.to_string_with(options.null_chr),
+            rating,
+            rating.steps
+        )?;
+    }
+    Ok(true)
+}
+
+fn heuristics(options: &Options) -> Heuristics {
+    Heuristics {
+        seed: seed(options),
+        ..options.heuristics.clone()
+    }
+}
+
+fn seed(options: &Options) -> u64 {
+    options.seed.unwrap_or_else(|| {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |time| time.as_nanos() as u64)
+    })
+}
+
+fn generate(options: &Options, out: impl Write) -> Result<bool, Error> {
+    let mut generator = Generator::new(seed(options));
+    generator.symmetric = options.symmetric;
+
+    let mut output = Output::new(out, options.to, options.null_chr)?;
+    for puzzle in generator.take(options.count) {
+        output.write(&puzzle)?;
+    }
+    output.flush()?;
+    Ok(true)
+}
+
+fn convert(options: &Options, inputs: &mut Inputs, out: impl Write) -> Result<bool, Error> {
+    let mut output = Output::new(out, options.to, options.null_chr)?;
+    for input in inputs {
+        output.write(&input.grid)?;
+    }
+    output.flush()?;
+    Ok(true)
+}
+
+fn canonical(options: &Options, inputs: &mut Inputs, out: &mut impl Write) -> Result<bool, Error> {
+    for input in inputs {
+        writeln!(out, "{}", input.grid.canonical()?.to_string_with(options.null_chr))?;
+    }
+    Ok(true)
+}
+
+fn dedup(options: &Options, inputs: &mut Inputs, out: &mut impl Write) -> Result<bool, Error> {
+    let mut seen = Deduplicator::new();
+    for input in inputs {
+        if seen.insert(&input.grid)? {
+            writeln!(out, "{}", input.grid.to_string_with(options.null_chr))?;
+        }
+    }
+    Ok(true)
+}
+
+fn minimal(options: &Options, inputs: &mut Inputs, out: &mut impl Write) -> Result<bool, Error> {
+    let mut ok = true;
+    for input in inputs {
+        match input.grid.redundant_clues() {
+            Ok(clues) if clues.is_empty() => writeln!(out, "{}", input.grid.to_string_with(options.null_chr))?,
+            Ok(clues) => {
+                let clues = clues
+                    .iter()
+                    .map(|(row, col)| format!("r{}c{}", row + 1, col + 1))
+                    .collect::<Vec<_>>();
+                eprintln!(
+                    "{}: puzzle {}: redundant clues {}",
+                    input.source,
+                    input.index,
+                    clues.join(", ")
+                );
+                ok = false;
+            }
+            Err(err) => {
+                eprintln!("{}: puzzle {}: {}", input.source, input.index, err);
+                ok = false;
+            }
+        }
+    }
+    Ok(ok)
+}
+
+fn minimize(options: &Options, inputs: &mut Inputs, out: &mut impl Write) -> Result<bool, Error> {
+    let symmetry = options.symmetric.then_some(Symmetry::Rotate180);
+    let mut ok = true;
+    for input in inputs {
+        match input.grid.minimize(symmetry) {
+            Ok(puzzle) => writeln!(out, "{}", puzzle.to_string_with(options.null_chr))?,
+            Err(err) => {
+                eprintln!("{}: puzzle {}: {}", input.source, input.index, err);
+                ok = false;
+            }
+        }
+    }
+    Ok(ok)
+}
+
+fn symmetry(options: &Options, inputs: &mut Inputs, out: &mut impl Write) -> Result<bool, Error> {
+    let join = |names: Vec<String>| {
+        if names.is_empty() {
+            "none".to_string()
+        } else {
+            names.join(", ")
+        }
+    };
+    let mut ok = true;
+    for input in inputs {
+        let clues = input.grid.clue_symmetries();
+        let digits = input.grid.symmetries();
+        ok &= !clues.is_empty();
+        writeln!(
+            out,
+            "{}\t{}\t{}\t{}",
+            input.grid.to_string_with(options.null_chr),
+            join(clues.iter().map(|symmetry| symmetry.to_string()).collect()),
+            join(
+                digits
+                    .iter()
+                    .map(|(symmetry, digits)| {
+                        let digits = digits.iter().map(|digit| digit.to_string()).collect::<String>();
+                        format!("{} ({})", symmetry, digits)
+                    })
+                    .collect()
+            ),
+            input.grid.automorphisms()?.len()
+        )?;
+    }
+    Ok(ok)
+}
+
+fn backdoor(options: &Options, inputs: &mut Inputs, out: &mut impl Write) -> Result<bool, Error> {
+    let mut ok = true;
+    for input in inputs {
+        match input.grid.singles_backdoors(options.depth) {
+            Ok(Some(backdoors)) => {
+                let cells = backdoors
+                    .cells()
+                    .iter()
+                    .map(|(row, col)| format!("r{}c{}", row + 1, col + 1))
+                    .collect::<Vec<_>>();
+                writeln!(
+                    out,
+                    "{}\t{}\t{}",
+                    input.grid.to_string_with(options.null_chr),
+                    backdoors.size,
+                    if cells.is_empty() {
+                        "none".to_string()
+                    } else {
+                        cells.join(", ")
+                    }
+                )?;
+            }
+            Ok(None) => {
+                eprintln!(
+                    "{}: puzzle {}: no backdoor of at most {} cells",
+                    input.source, input.index, options.depth
+                );
+                ok = false;
+            }
+            Err(err) => {
+                eprintln!("{}: puzzle {}: {}", input.source, input.index, err);
+                ok = false;
+            }
+        }
+    }
+    Ok(ok)
+}
+
+fn backbone(options: &Options, inputs: &mut Inputs, out: &mut impl Write) -> Result<bool, Error> {
+    let mut ok = true;
+    for input in inputs {
+        match input.grid.backbone() {
+            Ok(backbone) => {
+                let fixed = backbone.cells().iter().filter(|&&value| value != 0).count();
+                writeln!(out, "{}\t{}", backbone.to_string_with(options.null_chr), fixed)?;
+            }
+            Err(err) => {
+                eprintln!("{}: puzzle {}: {}", input.source, input.index, err);
+                ok = false;
+            }
+        }
+    }
+    Ok(ok)
+}
+
+fn cnf(_: &Options, inputs: &mut Inputs, out: &mut impl Write) -> Result<bool, Error> {
+    for input in inputs {
+        input.grid.to_cnf().write_dimacs(&mut *out)?;
+    }
+    Ok(true)
+}
+
+// Models are not puzzles, so every file holds exactly one of them
+fn model(options: &Options, out: &mut impl Write) -> Result<u8, String> {
+    let mut files = options.files.clone();
+    if files.is_empty() {
+        files.push("-".to_string());
+    }
+    let mut ok = true;
+    for file in files {
+        let model = if file == "-" {
+            Grid::read_dimacs_model(io::stdin().lock())
+        } else {
+            let opened = File::open(&file).map_err(|err| format!("{}: {}", file, err))?;
+            Grid::read_dimacs_model(BufReader::new(opened))
+        };
+        let name = if file == "-" { "stdin" } else { file.as_str() };
+        match model {
+            Ok(solution) if Sudoku::from(solution).is_solved() => {
+                writeln!(out, "{}", solution).map_err(|err| err.to_string())?;
+            }
+            Ok(_) => {
+                eprintln!("{}: model is not a valid solution", name);
+                ok = false;
+            }
+            Err(err) => {
+                eprintln!("{}: {}", name, err);
+                ok = false;
+            }
+        }
+    }
+    out.flush().map_err(|err| err.to_string())?;
+    Ok(if ok { 0 } else { FAILED })
+}
+
+fn bench(options: &Options, inputs: &mut Inputs, out: &mut impl Write) -> Result<bool, Error> {
+    let puzzles = inputs.map(|input| input.grid).collect::<Vec<_>>();
+    let length = puzzles.len();
+
+    let now = Instant::now();
+    let results = solve_all(options, puzzles);
+    let elapsed = now.elapsed();
+    let unsolved = results.iter().filter(|(result, _)| result.is_err()).count();
+    let guesses = results.iter().map(|(_, stats)| stats.guesses).sum::<u64>();
+
+    writeln!(
+        out,
+        "| puzzles | seconds   | puzzles/s | us/puzzle | guesses/puzzle | unsolved |"
+    )?;
+    writeln!(
+        out,
+        "| {:<7} | {:<9.3} | {:<9.1} | {:<9.1} | {:<14.1} | {:<8} |",
+        length,
+        elapsed.as_secs_f64(),
+        length as f64 / elapsed.as_secs_f64(),
+        elapsed.as_micros() as f64 / length as f64,
+        guesses as f64 / length as f64,
+        unsolved
+    )?;
+    Ok(unsolved == 0)
+}
+
+// Result and solver statistics of every puzzle, runs on all cores when built with rayon
+#[cfg(feature = "rayon")]
+fn solve_all(options: &Options, puzzles: Vec<Grid>) -> Vec<(Result<Grid, Error>, SolverStats)> {
+    use rayon::prelude::*;
+
+    let seed = seed(options);
+    match options.solver {
+        SolverKind::WaveFunctionCollapse => {
+            let heuristics = heuristics(options);
+            suji::par_solve_batch_with_stats(puzzles, || {
+                WaveFunctionCollapseSolver::with_heuristics(heuristics.clone())
+            })
+            .collect()
+        }
+        SolverKind::BruteForce => suji::par_solve_batch_with_stats(puzzles, BruteForceSolver::new).collect(),
+        SolverKind::Sat => suji::par_solve_batch_with_stats(puzzles, SatSolver::new).collect(),
+        SolverKind::Annealing => suji::par_solve_batch_with_stats(puzzles, || AnnealingSolver::new(seed)).collect(),
+    }
+}
+
+#[cfg(not(feature = "rayon"))]
+fn solve_all(options: &Options, puzzles: Vec<Grid>) -> Vec<(Result<Grid, Error>, SolverStats)> {
+    let seed = seed(options);
+    match options.solver {
+        SolverKind::WaveFunctionCollapse => {
+            let solver = WaveFunctionCollapseSolver::with_heuristics(heuristics(options));
+            suji::solve_batch_with_stats(puzzles, solver).collect()
+        }
+        SolverKind::BruteForce => suji::solve_batch_with_stats(puzzles, BruteForceSolver::new()).collect(),
+        SolverKind::Sat => suji::solve_batch_with_stats(puzzles, SatSolver::new()).collect(),
+        SolverKind::Annealing => suji::solve_batch_with_stats(puzzles, AnnealingSolver::new(seed)).collect(),
+    }
+}
+
+#[cfg(feature = "tui")]
+fn play(options: &Options) -> Result<u8, String> {
+    use std::path::Path;
+    use suji::Game;
+
+    let mut resumed = None;
+    let mut game = match options.files.first() {
+        Some(file) if file != "-" => match Game::load_from(Path::new(file)) {
+            Ok(game) => {
+                resumed = Some(file.clone());
+                Some(game)
+            }
+            Err(Error::InvalidSave(_)) => None,
+            Err(err) => return Err(format!("{}: {}", file, err)),
+        },
+        _ => None,
+    };
+    if game.is_none() && !options.files.is_empty() {
+        let input = Inputs::open(options)?.next().ok_or("no puzzle in the input")?;
+        game = Some(Game::new(&input.grid));
+    }
+    let mut game = game.unwrap_or_else(|| {
+        let mut generator = Generator::new(seed(options));
+        generator.symmetric = options.symmetric;
+        Game::new(&generator.puzzle())
+    });
+
+    let save_path = options.output.clone().or(resumed).unwrap_or("suji.save".to_string());
+    suji::play(&mut game, Path::new(&save_path)).map_err(|err| err.to_string())?;
+    Ok(0)
+}
+
+#[cfg(not(feature = "tui"))]
+fn play(_: &Options) -> Result<u8, String> {
+    Err("suji was built without the tui feature".to_string())
+}
+
+fn run(options: &Options) -> Result<u8, String> {
+    let stdout = io::stdout();
+    let mut out = BufWriter::new(stdout.lock());
+
+    if options.command == "play" {
+        return play(options);
+    }
+    if options.command == "model" {
+        return model(options, &mut out);
+    }
+    if options.command == "generate" {
+        return Ok(finish(generate(options, &mut out), &mut out, false));
+    }
+
+    const COMMANDS: [&str; 14] = [
+        "solve",
+        "count",
+        "validate",
+        "rate",
+        "convert",
+        "canonical",
+        "dedup",
+        "minimal",
+        "minimize",
+        "symmetry",
+        "backdoor",
+        "backbone",
+        "cnf",
+        "bench",
+    ];
+    if !COMMANDS.contains(&options.command.as_str()) {
+        return Err(format!("unknown command '{}'", options.command));
+    }
+
+    let mut inputs = Inputs::open(options)?;
+    let result = match options.command.as_str() {
+        "solve" => solve(options, &mut inputs, &mut out),
+        "count" => count(options, &mut inputs, &mut out),
+        "validate" => validate(options, &mut inputs, &mut out),
+        "rate" => rate(options, &mut inputs, &mut out),
+        "convert" => convert(options, &mut inputs, &mut out),
+        "canonical" => canonical(options, &mut inputs, &mut out),
+        "dedup" => dedup(options, &mut inputs, &mut out),
+        "minimal" => minimal(options, &mut inputs, &mut out),
+        "minimize" => minimize(options, &mut inputs, &mut out),
+        "symmetry" => symmetry(options, &mut inputs, &mut out),
+        "backdoor" => backdoor(options, &mut inputs, &mut out),
+        "backbone" => backbone(options, &mut inputs, &mut out),
+        "cnf" => cnf(options, &mut inputs, &mut out),
+        "bench" => bench(options, &mut inputs, &mut out),
+        _ => unreachable!(),
+    };
+    Ok(finish(result, &mut out, inputs.failed))
+}
+
+fn finish(result: Result<bool, Error>, out: &mut impl Write, bad_input: bool) -> u8 {
+    let result = result.and_then(|ok| {
+        out.flush()?;
+        Ok(ok)
+    });
+    match result {
+        Ok(_) if bad_input => BAD_INPUT,
+        Ok(true) => 0,
+        Ok(false) => FAILED,
+        // the reader of our output went away, e.g. `suji generate | head`
+        Err(Error::Io(err)) if err.kind() == io::ErrorKind::BrokenPipe => 0,
+        Err(err) => {
+            eprintln!("suji: {}", err);
+            BAD_INPUT
+        }
+    }
+}
+
+fn main() -> ExitCode {
+    let options = match parse_args(std::env::args().skip(1)) {
+        Ok(options) => options,
+        Err(err) => {
+            eprintln!("suji: {}\n\n{}", err, USAGE);
+            return ExitCode::from(BAD_INPUT);
+        }
+    };
+    if options.command.is_empty() || options.command == "help" {
+        println!("{}", USAGE);
+        return ExitCode::from(if options.command.is_empty() { BAD_INPUT } else { 0 });
+    }
+
+    match run(&options) {
+        Ok(code) => ExitCode::from(code),
+        Err(err) => {
+            eprintln!("suji: {}", err);
+            ExitCode::from(BAD_INPUT)
+        }
+    }
+}
@@ -0,0 +1,359 @@
+use crate::error::Error;
+use crate::grid::Grid;
+use crate::solutions::UNITS;
+use std::io::{self, Read, Write};
+
+// Binary layouts
+//
+// Solution (24 bytes): every row of a solved grid is a permutation of 1..=9 and is stored as its
+// rank among all 9! permutations in 19 bits. The three rows of a band are packed into one
+// little endian u64, so every band takes 8 bytes.
+//
+// Puzzle (11 + ceil(clues / 2) bytes): an 81 bit clue mask (cell i is bit i % 8 of byte i / 8)
+// followed by the clue values in reading order, two 4 bit values per byte, low nibble first.
+//
+// Stream: an 8 byte header (MAGIC, version, kind, 2 reserved bytes) followed by the records.
+// Puzzle records know their own length from the clue mask so no framing is needed.
+
+const SOLUTION_LEN: usize = 24;
+const MASK_LEN: usize = 11;
+const MAGIC: &[u8; 4] = b"SUJI";
+const VERSION: u8 = 1;
+const ROW_BITS: u32 = 19;
+const FACTORIALS: [u32; 9] = [1, 1, 2, 6, 24, 120, 720, 5040, 40320];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecordKind {
+    Puzzle,
+    Solution,
+}
+
+impl Grid {
+    pub fn encode_puzzle(&self) -> Vec<u8> {
+        let clues = self.cells().iter().filter(|&&value| value != 0).count();
+        let mut bytes = vec![0; MASK_LEN + clues.div_ceil(2)];
+
+        let mut k = 0;
+        for (i, &value) in self.cells().iter().enumerate() {
+            if value == 0 {
+                continue;
+            }
+            bytes[i / 8] |= 1 << (i % 8);
+            bytes[MASK_LEN + k / 2] |= value << (4 * (k % 2));
+            k += 1;
+        }
+        bytes
+    }
+
+    pub fn decode_puzzle(bytes: &[u8]) -> Result<Self, Error> {
+        if bytes.len() < MASK_LEN {
+            return Err(Error::InvalidLength(bytes.len()));
+        }
+        let clues = clue_count(&bytes[..MASK_LEN])?;
+        if bytes.len() != MASK_LEN + clues.div_ceil(2) {
+            return Err(Error::InvalidLength(bytes.len()));
+        }
+
+        let mut cells = [0; 81];
+        let mut k = 0;
+        for (i, cell) in cells.iter_mut().enumerate() {
+            if bytes[i / 8] & 1 << (i % 8) == 0 {
+                continue;
+            }
+            let value = bytes[MASK_LEN + k / 2] >> (4 * (k % 2)) & 0xF;
+            if !(1..=9).contains(&value) {
+                return Err(Error::InvalidEncoding("clue value out of range"));
+            }
+            *cell = value;
+            k += 1;
+        }
+        if clues % 2 == 1 && bytes[bytes.len() - 1] >> 4 != 0 {
+            return Err(Error::InvalidEncoding("non zero padding"));
+        }
+        Ok(Self::from_cells(cells))
+    }
+
+    // Only complete and valid grids can be encoded as a solution
+    pub fn encode_solution(&self) -> Result<[u8; SOLUTION_LEN], Error> {
+        if !is_solution(self) {
+            return Err(Error::InvalidSolution);
+        }
+        let mut bytes = [0; SOLUTION_LEN];
+        for band in 0..3 {
+            let mut packed = 0u64;
+            for r in 0..3 {
+                let row = &self.cells()[(band * 3 + r) * 9..(band * 3 + r) * 9 + 9];
+                let rank = permutation_rank(row).ok_or(Error::InvalidSolution)?;
+                packed |= (rank as u64) << (r as u32 * ROW_BITS);
+            }
+            bytes[band * 8..band * 8 + 8].copy_from_slice(&packed.to_le_bytes());
+        }
+        Ok(bytes)
+    }
+
+    pub fn decode_solution(bytes: &[u8]) -> Result<Self, Error> {
+        if bytes.len() != SOLUTION_LEN {
+            return Err(Error::InvalidLength(bytes.len()));
+        }
+        let mut cells = [0; 81];
+        for band in 0..3 {
+            let mut packed = [0; 8];
+            packed.copy_from_slice(&bytes[band * 8..band * 8 + 8]);
+            let packed = u64::from_le_bytes(packed);
+            if packed >> (3 * ROW_BITS) != 0 {
+                return Err(Error::InvalidEncoding("non zero padding"));
+            }
+            for r in 0..3 {
+                let rank = (packed >> (r as u32 * ROW_BITS)) as u32 & ((1 << ROW_BITS) - 1);
+                let row = permutation_unrank(rank).ok_or(Error::InvalidEncoding("row rank out of range"))?;
+                cells[(band * 3 + r) * 9..(band * 3 + r) * 9 + 9].copy_from_slice(&row);
+            }
+        }
+        // valid rows can still repeat digits in a column or box
+        let grid = Self::from_cells(cells);
+        if !is_solution(&grid) {
+            return Err(Error::InvalidSolution);
+        }
+        Ok(grid)
+    }
+}
+
+// Every row, column and box holds each digit exactly once
+fn is_solution(grid: &Grid) -> bool {
+    UNITS.iter().all(|unit| {
+        let mask = unit.iter().fold(0u16, |mask, &cell| {
+            mask | 1u16.checked_shl(grid.cells()[cell] as u32).unwrap_or(1)
+        });
+        mask == 0x3FE
+    })
+}
+
+pub struct BinaryWriter<W> {
+    writer: W,
+    kind: RecordKind,
+}
+
+impl<W: Write> BinaryWriter<W> {
+    pub fn new(mut writer: W, kind: RecordKind) -> io::Result<Self> {
+        let kind_byte = match kind {
+            RecordKind::Puzzle => 0,
+            RecordKind::Solution => 1,
+        };
+        writer.write_all(MAGIC)?;
+        writer.write_all(&[VERSION, kind_byte, 0, 0])?;
+        Ok(Self {
+            writer,
+            kind,
+        })
+    }
+
+    pub fn write(&mut self, grid: &Grid) -> Result<(), Error> {
+        match self.kind {
+            RecordKind::Puzzle => self.writer.write_all(&grid.encode_puzzle())?,
+            RecordKind::Solution => self.writer.write_all(&grid.encode_solution()?)?,
+        }
+        Ok(())
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+pub struct BinaryReader<R> {
+    reader: R,
+    kind: RecordKind,
+    buffer: Vec<u8>,
+}
+
+impl<R: Read> BinaryReader<R> {
+    pub fn new(mut reader: R) -> Result<Self, Error> {
+        let mut header = [0; 8];
+        reader.read_exact(&mut header)?;
+        if &header[..4] != MAGIC {
+            return Err(Error::InvalidEncoding("missing stream header"));
+        }
+        if header[4] != VERSION {
+            return Err(Error::InvalidEncoding("unsupported stream version"));
+        }
+        let kind = match header[5] {
+            0 => RecordKind::Puzzle,
+            1 => RecordKind::Solution,
+            _ => return Err(Error::InvalidEncoding("unknown record kind")),
+        };
+        Ok(Self {
+            reader,
+            kind,
+            buffer: Vec::with_capacity(MASK_LEN + 41),
+        })
+    }
+
+    pub fn kind(&self) -> RecordKind {
+        self.kind
+    }
+
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+
+    // Reads the first bytes of a record, returns false on a clean end of stream
+    fn read_start(&mut self, len: usize) -> Result<bool, Error> {
+        self.buffer.resize(len, 0);
+        let mut read = 0;
+        while read < len {
+            match self.reader.read(&mut self.buffer[read..]) {
+                Ok(0) if read == 0 => return Ok(false),
+                Ok(0) => return Err(Error::InvalidEncoding("truncated record")),
+                Ok(n) => read += n,
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+                Err(err) => return Err(err.into()),
+            }
+        }
+        Ok(true)
+    }
+
+    fn read_record(&mut self) -> Result<Option<Grid>, Error> {
+        match self.kind {
+            RecordKind::Solution => {
+                if !self.read_start(SOLUTION_LEN)? {
+                    return Ok(None);
+                }
+                Grid::decode_solution(&self.buffer).map(Some)
+            }
+            RecordKind::Puzzle => {
+                if !self.read_start(MASK_LEN)? {
+                    return Ok(None);
+                }
+                let len = MASK_LEN + clue_count(&self.buffer)?.div_ceil(2);
+                self.buffer.resize(len, 0);
+                self.reader
+                    .read_exact(&mut self.buffer[MASK_LEN..])
+                    .map_err(|err| match err.kind() {
+                        io::ErrorKind::UnexpectedEof => Error::InvalidEncoding("truncated record"),
+                        _ => err.into(),
+                    })?;
+                Grid::decode_puzzle(&self.buffer).map(Some)
+            }
+        }
+    }
+}
+
+impl<R: Read> Iterator for BinaryReader<R> {
+    type Item = Result<Grid, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read_record().transpose()
+    }
+}
+
+fn clue_count(mask: &[u8]) -> Result<usize, Error> {
+    // only the lowest bit of the last byte belongs to the grid
+    if mask[MASK_LEN - 1] & !1 != 0 {
+        return Err(Error::InvalidEncoding("clue mask out of range"));
+    }
+    Ok(mask.iter().map(|byte| byte.count_ones() as usize).sum())
+}
+
+fn permutation_rank(row: &[u8]) -> Option<u32> {
+    let mut used = 0u16;
+    let mut rank = 0;
+    for (i, &value) in row.iter().enumerate() {
+        if !(1..=9).contains(&value) || used & 1 << value != 0 {
+            return None;
+        }
+        let smaller_unused = (1..value).filter(|&v| used & 1 << v == 0).count() as u32;
+        rank += smaller_unused * FACTORIALS[8 - i];
+        used |= 1 << value;
+    }
+    Some(rank)
+}
+
+fn permutation_unrank(mut rank: u32) -> Option<[u8; 9]> {
+    if rank >= FACTORIALS[8] * 9 {
+        return None;
+    }
+    let mut remaining = (1..=9).collect::<Vec<u8>>();
+    let mut row = [0; 9];
+    for (i, cell) in row.iter_mut().enumerate() {
+        let idx = (rank / FACTORIALS[8 - i]) as usize;
+        rank %= FACTORIALS[8 - i];
+        *cell = remaining.remove(idx);
+    }
+    Some(row)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PUZZLE: &str = "000000907000420180000705026100904000050000040000507009920108000034059000507000000";
+
+    fn puzzle() -> Grid {
+        PUZZLE.parse().unwrap()
+    }
+
+    #[test]
+    fn puzzles_round_trip() {
+        let bytes = puzzle().encode_puzzle();
+        assert_eq!(bytes.len(), MASK_LEN + 14);
+        assert_eq!(Grid::decode_puzzle(&bytes).unwrap(), puzzle());
+        assert_eq!(Grid::decode_puzzle(&Grid::new().encode_puzzle()).unwrap(), Grid::new());
+    }
+
+    #[test]
+    fn solutions_round_trip() {
+        let solution = puzzle().unique_solution().unwrap();
+        let bytes = solution.encode_solution().unwrap();
+        assert_eq!(Grid::decode_solution(&bytes).unwrap(), solution);
+        assert!(matches!(puzzle().encode_solution(), Err(Error::InvalidSolution)));
+    }
+
+    #[test]
+    fn corrupted_bytes_are_rejected() {
+        let mut bytes = puzzle().encode_puzzle();
+        assert!(matches!(
+            Grid::decode_puzzle(&bytes[..bytes.len() - 1]),
+            Err(Error::InvalidLength(_))
+        ));
+        bytes[MASK_LEN - 1] |= 2;
+        assert!(matches!(Grid::decode_puzzle(&bytes), Err(Error::InvalidEncoding(_))));
+
+        let mut bytes = puzzle().encode_puzzle();
+        bytes[MASK_LEN] = 0xAA;
+        assert!(matches!(Grid::decode_puzzle(&bytes), Err(Error::InvalidEncoding(_))));
+
+        // every row is still a permutation but the columns repeat digits
+        let solution = puzzle().unique_solution().unwrap();
+        let mut bytes = solution.encode_solution().unwrap();
+        bytes.copy_within(0..8, 8);
+        assert!(matches!(Grid::decode_solution(&bytes), Err(Error::InvalidSolution)));
+        bytes[7] = 0xFF;
+        assert!(matches!(Grid::decode_solution(&bytes), Err(Error::InvalidEncoding(_))));
+    }
+
+    #[test]
+    fn streams_round_trip() {
+        let grids = [puzzle(), Grid::new(), "1".repeat(81).parse().unwrap()];
+        let mut writer = BinaryWriter::new(Vec::new(), RecordKind::Puzzle).unwrap();
+        for grid in &grids {
+            writer.write(grid).unwrap();
+        }
+        let mut bytes = writer.into_inner();
+
+        let reader = BinaryReader::new(&bytes[..]).unwrap();
+        assert_eq!(reader.kind(), RecordKind::Puzzle);
+        assert_eq!(reader.collect::<Result<Vec<_>, _>>().unwrap(), grids);
+
+        bytes.pop();
+        let last = BinaryReader::new(&bytes[..]).unwrap().last().unwrap();
+        assert!(matches!(last, Err(Error::InvalidEncoding("truncated record"))));
+        assert!(matches!(
+            BinaryReader::new(&b"SUJI\x09\0\0\0"[..]),
+            Err(Error::InvalidEncoding(_))
+        ));
+    }
+}
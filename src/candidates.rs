@@ -0,0 +1,184 @@
+use crate::error::Error;
+use crate::grid::Grid;
+use crate::{notation, Sudoku};
+use std::fmt;
+use std::str::FromStr;
+
+const ALL_CANDIDATES: u16 = 0b111111111;
+const COMPACT_LEN: usize = (81 * 9usize).div_ceil(8);
+
+// Candidate state of a whole grid. Every cell holds a bitmask where bit n stands for the value
+// n + 1, a placed value is represented by a single candidate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Candidates {
+    cells: [u16; 81],
+}
+
+impl Candidates {
+    pub fn new() -> Self {
+        Self {
+            cells: [ALL_CANDIDATES; 81],
+        }
+    }
+
+    pub fn from_masks(cells: [u16; 81]) -> Self {
+        Self {
+            cells: cells.map(|mask| mask & ALL_CANDIDATES),
+        }
+    }
+
+    // Takes the candidates stored in the sudoku, including manual eliminations
+    pub fn from_sudoku(sudoku: &Sudoku) -> Self {
+        let mut cells = [0; 81];
+        for (i, cell) in cells.iter_mut().enumerate() {
+            *cell = match sudoku.get(i / 9, i % 9) {
+                0 => sudoku.get_candidates(i / 9, i % 9),
+                value => 1 << (value - 1),
+            };
+        }
+        Self {
+            cells,
+        }
+    }
+
+    pub fn masks(&self) -> &[u16; 81] {
+        &self.cells
+    }
+
+    #[inline]
+    pub fn get(&self, row: usize, col: usize) -> u16 {
+        self.cells[row * 9 + col]
+    }
+
+    #[inline]
+    pub fn set(&mut self, row: usize, col: usize, mask: u16) {
+        self.cells[row * 9 + col] = mask & ALL_CANDIDATES;
+    }
+
+    #[inline]
+    pub fn contains(&self, row: usize, col: usize, value: u8) -> bool {
+        self.cells[row * 9 + col] & 1 << (value - 1) != 0
+    }
+
+    #[inline]
+    pub fn insert(&mut self, row: usize, col: usize, value: u8) {
+        self.cells[row * 9 + col] |= 1 << (value - 1);
+    }
+
+    #[inline]
+    pub fn remove(&mut self, row: usize, col: usize, value: u8) {
+        self.cells[row * 9 + col] &= !(1 << (value - 1));
+    }
+
+    // Cells with a single candidate are reported as placed values
+    pub fn to_grid(&self) -> Grid {
+        let mut grid = Grid::new();
+        for (i, &mask) in self.cells.iter().enumerate() {
+            if mask.count_ones() == 1 {
+                grid.set(i / 9, i % 9, mask.trailing_zeros() as u8 + 1);
+            }
+        }
+        grid
+    }
+
+    // Packs the 81 x 9 candidate bits row major into 92 bytes, cell 0 value 1 is the lowest bit
+    // of the first byte
+    pub fn to_bytes(&self) -> [u8; COMPACT_LEN] {
+        let mut bytes = [0; COMPACT_LEN];
+        for (i, &mask) in self.cells.iter().enumerate() {
+            for bit in 0..9 {
+                if mask & 1 << bit != 0 {
+                    let idx = i * 9 + bit;
+                    bytes[idx / 8] |= 1 << (idx % 8);
+                }
+            }
+        }
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        if bytes.len() != COMPACT_LEN {
+            return Err(Error::InvalidLength(bytes.len()));
+        }
+        let mut cells = [0; 81];
+        for (i, cell) in cells.iter_mut().enumerate() {
+            for bit in 0..9 {
+                let idx = i * 9 + bit;
+                if bytes[idx / 8] & 1 << (idx % 8) != 0 {
+                    *cell |= 1 << bit;
+                }
+            }
+        }
+        Ok(Self {
+            cells,
+        })
+    }
+
+    // A cell without any candidate is written as 0
+    pub(crate) fn cell_string(&self, i: usize) -> String {
+        if self.cells[i] == 0 {
+            return "0".to_string();
+        }
+        (1..=9u8)
+            .filter(|&value| self.cells[i] & 1 << (value - 1) != 0)
+            .map(|value| (b'0' + value) as char)
+            .collect()
+    }
+}
+
+impl Default for Candidates {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FromStr for Candidates {
+    type Err = Error;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        Ok(Self {
+            cells: notation::parse_pencil_marks(input)?,
+        })
+    }
+}
+
+// Writes the 9x9 pencil-mark layout used by HoDoKu and SudokuWiki
+impl fmt::Display for Candidates {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let strings = (0..81).map(|i| self.cell_string(i)).collect::<Vec<_>>();
+        let widths = (0..9)
+            .map(|col| (0..9).map(|row| strings[row * 9 + col].len()).max().unwrap_or(0).max(1))
+            .collect::<Vec<_>>();
+        let stack_widths = (0..3)
+            .map(|stack| widths[stack * 3..stack * 3 + 3].iter().sum::<usize>() + 6)
+            .collect::<Vec<_>>();
+
+        let rule = |edge: char, junction: char| {
+            let mut line = String::new();
+            line.push(edge);
+            for (stack, width) in stack_widths.iter().enumerate() {
+                line.push_str(&"-".repeat(*width));
+                line.push(if stack == 2 { edge } else { junction });
+            }
+            line
+        };
+
+        writeln!(f, "{}", rule('.', '.'))?;
+        for row in 0..9 {
+            write!(f, "|")?;
+            for col in 0..9 {
+                let gap = if col % 3 == 0 { " " } else { "  " };
+                write!(f, "{}{:<width$}", gap, strings[row * 9 + col], width = widths[col])?;
+                if col % 3 == 2 {
+                    write!(f, " |")?;
+                }
+            }
+            writeln!(f)?;
+            if row == 2 || row == 5 {
+                writeln!(f, "{}", rule(':', '+'))?;
+            }
+        }
+        write!(f, "{}", rule('\'', '\''))?;
+        Ok(())
+    }
+}
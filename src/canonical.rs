@@ -0,0 +1,297 @@
+use crate::error::Error;
+use crate::grid::Grid;
+use crate::transform::Transform;
+use std::cmp::Ordering;
+use std::collections::HashSet;
+use std::sync::OnceLock;
+
+const PERMS3: [[usize; 3]; 6] = [[0, 1, 2], [0, 2, 1], [1, 0, 2], [1, 2, 0], [2, 0, 1], [2, 1, 0]];
+
+// All 6^4 column orders that keep columns inside their stacks
+fn column_orders() -> &'static [[usize; 9]] {
+    static ORDERS: OnceLock<Vec<[usize; 9]>> = OnceLock::new();
+    ORDERS.get_or_init(|| {
+        let mut orders = Vec::with_capacity(1296);
+        for stacks in PERMS3 {
+            for first in PERMS3 {
+                for second in PERMS3 {
+                    for third in PERMS3 {
+                        let inner = [first, second, third];
+                        let mut order = [0; 9];
+                        for (i, col) in order.iter_mut().enumerate() {
+                            *col = stacks[i / 3] * 3 + inner[i / 3][i % 3];
+                        }
+                        orders.push(order);
+                    }
+                }
+            }
+        }
+        orders
+    })
+}
+
+// Digits are relabeled in order of their first appearance, which makes the labels of the first
+// rows independent of the original digits
+#[derive(Clone, Copy)]
+struct Labels {
+    labels: [u8; 10],
+    next: u8,
+}
+
+impl Labels {
+    fn new() -> Self {
+        Self {
+            labels: [0; 10],
+            next: 1,
+        }
+    }
+
+    #[inline]
+    fn get(&mut self, value: u8) -> u8 {
+        if value == 0 {
+            return 0;
+        }
+        if self.labels[value as usize] == 0 {
+            self.labels[value as usize] = self.next;
+            self.next += 1;
+        }
+        self.labels[value as usize]
+    }
+
+    // Digits that never appeared get the remaining labels in increasing order
+    fn digits(mut self) -> [u8; 9] {
+        let mut digits = [0; 9];
+        for (i, digit) in digits.iter_mut().enumerate() {
+            *digit = self.get(i as u8 + 1);
+        }
+        digits
+    }
+}
+
+#[derive(Clone, Copy)]
+struct Candidate {
+    transpose: bool,
+    rows: [usize; 9],
+    cols: [usize; 9],
+    labels: Labels,
+}
+
+// Writes the relabeled rows into out while comparing them to best, gives up as soon as the
+// result can only be larger
+fn compare_rows(
+    cells: &[[u8; 9]; 9],
+    candidate: &mut Candidate,
+    rows: &[usize],
+    best: &[u8],
+    out: &mut Vec<u8>,
+) -> Ordering {
+    out.clear();
+    let mut ordering = if best.is_empty() {
+        Ordering::Less
+    } else {
+        Ordering::Equal
+    };
+    for &row in rows {
+        for &col in &candidate.cols {
+            let value = candidate.labels.get(cells[row][col]);
+            if ordering == Ordering::Equal {
+                ordering = value.cmp(&best[out.len()]);
+                if ordering == Ordering::Greater {
+                    return ordering;
+                }
+            }
+            out.push(value);
+        }
+    }
+    ordering
+}
+
+// The lexicographically minimal grid (empty cells as 0, read row by row) among all grids
+// equivalent to this one, together with every transform that produces it. Fails for cell values
+// above 9, which have no label.
+pub(crate) fn canonical_transforms(grid: &Grid) -> Result<(Grid, Vec<Transform>), Error> {
+    grid.check_values()?;
+    let sources: [[[u8; 9]; 9]; 2] = [
+        std::array::from_fn(|row| std::array::from_fn(|col| grid.get(row, col))),
+        std::array::from_fn(|row| std::array::from_fn(|col| grid.get(col, row))),
+    ];
+
+    // The first row only depends on where the clues are, as its digits are always labeled
+    // 1, 2, 3... Pick the rows that allow the most empty cells in front.
+    let pattern = |cells: &[u8; 9], cols: &[usize; 9]| {
+        cols.iter()
+            .fold(0u16, |pattern, &col| pattern << 1 | (cells[col] != 0) as u16)
+    };
+    let min_pattern = |cells: &[u8; 9]| {
+        let mut counts = [0, 1, 2].map(|stack| (0..3).filter(|&i| cells[stack * 3 + i] != 0).count());
+        counts.sort_unstable();
+        counts
+            .iter()
+            .fold(0u16, |pattern, &count| pattern << 3 | ((1 << count) - 1))
+    };
+    let mut best_pattern = u16::MAX;
+    let mut firsts = Vec::new();
+    for (transpose, cells) in sources.iter().enumerate() {
+        for (row, cells) in cells.iter().enumerate() {
+            let pattern = min_pattern(cells);
+            if pattern < best_pattern {
+                best_pattern = pattern;
+                firsts.clear();
+            }
+            if pattern == best_pattern {
+                firsts.push((transpose, row));
+            }
+        }
+    }
+
+    // The top band: every column order giving the first row its pattern, with both orders of
+    // the remaining two rows of the band
+    let mut best = Vec::new();
+    let mut out = Vec::with_capacity(54);
+    let mut candidates = Vec::new();
+    for &(transpose, first) in &firsts {
+        let cells = &sources[transpose];
+        let band = first / 3;
+        let others = [0, 1, 2].map(|i| band * 3 + i);
+        let others = others.iter().copied().filter(|&row| row != first).collect::<Vec<_>>();
+        for cols in column_orders() {
+            if pattern(&cells[first], cols) != best_pattern {
+                continue;
+            }
+            for (second, third) in [(others[0], others[1]), (others[1], others[0])] {
+                let mut candidate = Candidate {
+                    transpose: transpose == 1,
+                    rows: [first, second, third, 0, 0, 0, 0, 0, 0],
+                    cols: *cols,
+                    labels: Labels::new(),
+                };
+                match compare_rows(cells, &mut candidate, &[first, second, third], &best, &mut out) {
+                    Ordering::Less => {
+                        best.clone_from(&out);
+                        candidates.clear();
+                        candidates.push(candidate);
+                    }
+                    Ordering::Equal => candidates.push(candidate),
+                    Ordering::Greater => {}
+                }
+            }
+        }
+    }
+
+    // The other two bands in both orders with every order of their rows
+    let top = best;
+    let mut best = Vec::new();
+    let mut transforms = Vec::new();
+    for candidate in &candidates {
+        let cells = &sources[candidate.transpose as usize];
+        let band = candidate.rows[0] / 3;
+        let others = [0, 1, 2].iter().copied().filter(|&b| b != band).collect::<Vec<usize>>();
+        for (middle, bottom) in [(others[0], others[1]), (others[1], others[0])] {
+            for middle_order in PERMS3 {
+                for bottom_order in PERMS3 {
+                    let mut candidate = *candidate;
+                    for i in 0..3 {
+                        candidate.rows[3 + i] = middle * 3 + middle_order[i];
+                        candidate.rows[6 + i] = bottom * 3 + bottom_order[i];
+                    }
+                    let rows = candidate.rows;
+                    match compare_rows(cells, &mut candidate, &rows[3..], &best, &mut out) {
+                        Ordering::Less => {
+                            best.clone_from(&out);
+                            transforms.clear();
+                        }
+                        Ordering::Equal => {}
+                        Ordering::Greater => continue,
+                    }
+                    transforms.push(Transform::from_parts(
+                        candidate.transpose,
+                        candidate.rows,
+                        candidate.cols,
+                        candidate.labels.digits(),
+                    ));
+                }
+            }
+        }
+    }
+
+    let mut cells = [0; 81];
+    cells[..27].copy_from_slice(&top);
+    cells[27..].copy_from_slice(&best);
+    Ok((Grid::from_cells(cells), transforms))
+}
+
+impl Grid {
+    // Minimal lexicographic representative of all grids equivalent to this one, two puzzles are
+    // isomorphic exactly when their canonical forms are equal
+    pub fn canonical(&self) -> Result<Grid, Error> {
+        Ok(canonical_transforms(self)?.0)
+    }
+
+    // A transform that maps this grid to its canonical form
+    pub fn canonical_transform(&self) -> Result<Transform, Error> {
+        Ok(canonical_transforms(self)?.1[0])
+    }
+}
+
+// Filters a stream of puzzles down to one puzzle per isomorphism class
+#[derive(Debug, Clone, Default)]
+pub struct Deduplicator {
+    seen: HashSet<Grid>,
+}
+
+impl Deduplicator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Returns false if an equivalent puzzle was inserted before
+    pub fn insert(&mut self, grid: &Grid) -> Result<bool, Error> {
+        Ok(self.seen.insert(grid.canonical()?))
+    }
+
+    pub fn len(&self) -> usize {
+        self.seen.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.seen.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PUZZLE: &str = "530070000600195000098000060800060003400803001700020006060000280000419005000080079";
+
+    #[test]
+    fn transformed_puzzles_share_the_canonical_form() {
+        let grid: Grid = PUZZLE.parse().unwrap();
+        let canonical = grid.canonical().unwrap();
+        assert_eq!(grid.canonical_transform().unwrap().apply(&grid), canonical);
+
+        let transform = Transform::from_parts(
+            true,
+            [5, 3, 4, 0, 2, 1, 8, 6, 7],
+            [2, 0, 1, 6, 8, 7, 3, 4, 5],
+            [9, 4, 1, 7, 2, 8, 3, 5, 6],
+        );
+        let moved = transform.apply(&grid);
+        assert_ne!(moved, grid);
+        assert_eq!(moved.canonical().unwrap(), canonical);
+        assert_eq!(canonical.canonical().unwrap(), canonical);
+
+        let mut seen = Deduplicator::new();
+        assert!(seen.insert(&grid).unwrap());
+        assert!(!seen.insert(&moved).unwrap());
+        assert_eq!(seen.len(), 1);
+    }
+
+    #[test]
+    fn values_above_nine_are_rejected() {
+        let mut grid: Grid = PUZZLE.parse().unwrap();
+        grid.set(0, 2, 12);
+        assert!(matches!(grid.canonical(), Err(Error::InvalidValue(12))));
+        assert!(Deduplicator::new().insert(&grid).is_err());
+    }
+}
@@ -0,0 +1,366 @@
+use crate::error::Error;
+use crate::grid::Grid;
+use crate::logic::combinations;
+use crate::solutions::UNITS;
+use crate::variants::Variants;
+use crate::Sudoku;
+use std::fmt;
+use std::io::{self, BufRead, Write};
+
+// Propositional encoding of a sudoku in conjunctive normal form. Variable `variable(row, col,
+// digit)` is true when the cell holds the digit, literals are DIMACS style signed integers.
+// Variant rules may add auxiliary variables after the first 729.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cnf {
+    pub variables: usize,
+    pub clauses: Vec<Vec<i32>>,
+}
+
+impl Cnf {
+    pub const VARIABLES: usize = 729;
+
+    // 1-based as required by DIMACS, digit is 1..=9
+    pub fn variable(row: usize, col: usize, digit: u8) -> i32 {
+        (row * 81 + col * 9 + digit as usize) as i32
+    }
+
+    // The cell and digit of a variable, None if it is out of range
+    pub fn cell_of(variable: i32) -> Option<(usize, usize, u8)> {
+        if !(1..=Self::VARIABLES as i32).contains(&variable) {
+            return None;
+        }
+        let index = variable as usize - 1;
+        Some((index / 81, index / 9 % 9, (index % 9) as u8 + 1))
+    }
+
+    // The rules of sudoku alone: every cell holds exactly one digit and every unit holds every
+    // digit exactly once
+    pub fn rules() -> Self {
+        Self::unit_rules(&UNITS)
+    }
+
+    // The rules with the units of the variants, cages add one auxiliary variable per digit
+    // combination that makes up their sum
+    pub fn rules_with(variants: &Variants) -> Result<Self, Error> {
+        let mut cnf = Self::unit_rules(&variants.units()?);
+        for (cage, cells) in variants.cages.iter().zip(variants.cage_cells()?) {
+            cnf.add_cage(&cells, cage.sum);
+        }
+        Ok(cnf)
+    }
+
+    fn unit_rules(units: &[[usize; 9]]) -> Self {
+        let mut clauses = Vec::with_capacity(81 * 37 + units.len() * 9 * 37);
+        for cell in 0..81 {
+            let (row, col) = (cell / 9, cell % 9);
+            clauses.push((1..=9).map(|digit| Self::variable(row, col, digit)).collect());
+            for a in 1..=9 {
+                for b in a + 1..=9 {
+                    clauses.push(vec![-Self::variable(row, col, a), -Self::variable(row, col, b)]);
+                }
+            }
+        }
+        for unit in units {
+            for digit in 1..=9 {
+                let literals = unit.map(|cell| Self::variable(cell / 9, cell % 9, digit));
+                clauses.push(literals.to_vec());
+                for (i, &a) in literals.iter().enumerate() {
+                    for &b in &literals[i + 1..] {
+                        clauses.push(vec![-a, -b]);
+                    }
+                }
+            }
+        }
+        Self {
+            variables: Self::VARIABLES,
+            clauses,
+        }
+    }
+
+    // Every digit at most once in the cage, and only digits of a combination adding up to the sum
+    fn add_cage(&mut self, cells: &[usize], sum: Option<u32>) {
+        for digit in 1..=9 {
+            for (i, &a) in cells.iter().enumerate() {
+                for &b in &cells[i + 1..] {
+                    self.clauses.push(vec![
+                        -Self::variable(a / 9, a % 9, digit),
+                        -Self::variable(b / 9, b % 9, digit),
+                    ]);
+                }
+            }
+        }
+        let Some(sum) = sum else {
+            return;
+        };
+
+        // with distinct digits in every cell the cage holds exactly the digits of its combination
+        let mut any = Vec::new();
+        for combination in combinations(&[1u8, 2, 3, 4, 5, 6, 7, 8, 9], cells.len()) {
+            if combination.iter().map(|&digit| digit as u32).sum::<u32>() != sum {
+                continue;
+            }
+            self.variables += 1;
+            let chosen = self.variables as i32;
+            any.push(chosen);
+            for digit in (1..=9).filter(|digit| !combination.contains(digit)) {
+                for &cell in cells {
+                    self.clauses
+                        .push(vec![-chosen, -Self::variable(cell / 9, cell % 9, digit)]);
+                }
+            }
+        }
+        // an impossible sum leaves an empty clause
+        self.clauses.push(any);
+    }
+
+    pub fn from_grid(grid: &Grid) -> Self {
+        let mut cnf = Self::rules();
+        cnf.add_givens(grid);
+        cnf
+    }
+
+    pub fn from_grid_with(grid: &Grid, variants: &Variants) -> Result<Self, Error> {
+        let mut cnf = Self::rules_with(variants)?;
+        cnf.add_givens(grid);
+        Ok(cnf)
+    }
+
+    fn add_givens(&mut self, grid: &Grid) {
+        for (cell, &value) in grid.cells().iter().enumerate() {
+            if value != 0 {
+                self.clauses.push(vec![Self::variable(cell / 9, cell % 9, value)]);
+            }
+        }
+    }
+
+    pub fn write_dimacs(&self, mut writer: impl Write) -> io::Result<()> {
+        write!(writer, "{}", self)
+    }
+}
+
+impl fmt::Display for Cnf {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "c suji sudoku encoding, variable 81 * row + 9 * col + digit")?;
+        writeln!(f, "p cnf {} {}", self.variables, self.clauses.len())?;
+        for clause in &self.clauses {
+            for literal in clause {
+                write!(f, "{} ", literal)?;
+            }
+            writeln!(f, "0")?;
+        }
+        Ok(())
+    }
+}
+
+impl Grid {
+    pub fn to_cnf(&self) -> Cnf {
+        Cnf::from_grid(self)
+    }
+
+    pub fn to_dimacs(&self) -> String {
+        self.to_cnf().to_string()
+    }
+
+    pub fn to_cnf_with(&self, variants: &Variants) -> Result<Cnf, Error> {
+        Cnf::from_grid_with(self, variants)
+    }
+
+    // Reads the output of a SAT solver run on `to_dimacs`, either the competition format
+    // (`s SATISFIABLE` and `v` lines) or a bare list of literals as written by minisat
+    pub fn read_dimacs_model(reader: impl BufRead) -> Result<Grid, Error> {
+        let mut cells = [0u8; 81];
+        for line in reader.lines() {
+            let line = line?;
+            let line = line.trim();
+            let literals = match line.split_whitespace().next() {
+                None | Some("c") => continue,
+                Some("s") | Some("SAT") | Some("UNSAT") | Some("UNSATISFIABLE") => {
+                    if line.contains("UNSAT") {
+                        return Err(Error::NoSolution);
+                    }
+                    continue;
+                }
+                Some("v") => &line[1..],
+                Some(_) => line,
+            };
+            for literal in literals.split_whitespace() {
+                let literal = literal
+                    .parse::<i32>()
+                    .map_err(|_| Error::InvalidModel("literal is not a number"))?;
+                // auxiliary variables of variant rules say nothing about the cells
+                let Some((row, col, digit)) = Cnf::cell_of(literal) else {
+                    continue;
+                };
+                let cell = &mut cells[row * 9 + col];
+                if *cell != 0 && *cell != digit {
+                    return Err(Error::InvalidModel("two digits in one cell"));
+                }
+                *cell = digit;
+            }
+        }
+        if cells.contains(&0) {
+            return Err(Error::InvalidModel("not every cell is assigned"));
+        }
+        Ok(Grid::from_cells(cells))
+    }
+
+    pub fn from_dimacs_model(model: &str) -> Result<Grid, Error> {
+        Self::read_dimacs_model(model.as_bytes())
+    }
+}
+
+impl Sudoku {
+    // Besides the placed digits, candidates eliminated on the sudoku become negative unit clauses
+    pub fn to_cnf(&self) -> Cnf {
+        let mut cnf = Cnf::from_grid(&self.to_grid());
+        self.add_eliminations(&mut cnf);
+        cnf
+    }
+
+    pub fn to_cnf_with(&self, variants: &Variants) -> Result<Cnf, Error> {
+        let mut cnf = Cnf::from_grid_with(&self.to_grid(), variants)?;
+        self.add_eliminations(&mut cnf);
+        Ok(cnf)
+    }
+
+    fn add_eliminations(&self, cnf: &mut Cnf) {
+        for cell in 0..81 {
+            let (row, col) = (cell / 9, cell % 9);
+            if self.get(row, col) != 0 {
+                continue;
+            }
+            // digits ruled out by the placed ones are already covered by the rules
+            let eliminated = self.get_possible_values(row, col) & !self.get_candidates(row, col);
+            for digit in 1..=9 {
+                if eliminated & 1 << (digit - 1) != 0 {
+                    cnf.clauses.push(vec![-Cnf::variable(row, col, digit)]);
+                }
+            }
+        }
+    }
+
+    pub fn to_dimacs(&self) -> String {
+        self.to_cnf().to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::variants::Cage;
+
+    const PUZZLE: &str = "000000907000420180000705026100904000050000040000507009920108000034059000507000000";
+
+    // Every variable of the cells, true when the solution holds the digit
+    fn model(solution: &Grid) -> Vec<i32> {
+        (0..81)
+            .flat_map(|cell| {
+                (1..=9).map(move |digit| {
+                    let variable = Cnf::variable(cell / 9, cell % 9, digit);
+                    if solution.cells()[cell] == digit {
+                        variable
+                    } else {
+                        -variable
+                    }
+                })
+            })
+            .collect()
+    }
+
+    fn satisfies(cnf: &Cnf, model: &[i32]) -> bool {
+        cnf.clauses
+            .iter()
+            .all(|clause| clause.iter().any(|literal| model.contains(literal)))
+    }
+
+    #[test]
+    fn solution_satisfies_the_encoding_and_reads_back() {
+        let puzzle = PUZZLE.parse::<Grid>().unwrap();
+        let solution = puzzle.unique_solution().unwrap();
+        let model = model(&solution);
+        assert!(satisfies(&puzzle.to_cnf(), &model));
+
+        let dimacs = puzzle.to_dimacs();
+        let header = format!("p cnf 729 {}", puzzle.to_cnf().clauses.len());
+        assert_eq!(dimacs.lines().nth(1), Some(header.as_str()));
+
+        let literals = model
+            .iter()
+            .map(|literal| literal.to_string())
+            .collect::<Vec<_>>()
+            .join(" ");
+        let competition = format!("c model\ns SATISFIABLE\nv {} 0\n", literals);
+        assert_eq!(Grid::from_dimacs_model(&competition).unwrap(), solution);
+        let minisat = format!("SAT\n{} 0\n", literals);
+        assert_eq!(Grid::from_dimacs_model(&minisat).unwrap(), solution);
+    }
+
+    #[test]
+    fn broken_models_are_rejected() {
+        assert!(matches!(
+            Grid::from_dimacs_model("s UNSATISFIABLE\n"),
+            Err(Error::NoSolution)
+        ));
+        assert!(matches!(
+            Grid::from_dimacs_model("v 1 x 0\n"),
+            Err(Error::InvalidModel(_))
+        ));
+        assert!(matches!(
+            Grid::from_dimacs_model("v 1 2 0\n"),
+            Err(Error::InvalidModel(_))
+        ));
+        assert!(matches!(
+            Grid::from_dimacs_model("v 1 0\n"),
+            Err(Error::InvalidModel(_))
+        ));
+    }
+
+    #[test]
+    fn variants_add_their_units_and_cages() {
+        let classic = Cnf::rules();
+        let diagonal = Cnf::rules_with(&Variants {
+            diagonals: true,
+            ..Variants::default()
+        })
+        .unwrap();
+        assert_eq!(diagonal.clauses.len(), classic.clauses.len() + 2 * 9 * 37);
+
+        // 1 + 2 is the only pair adding up to 3, 3 digits can never add up to 5
+        let cage = |cells: Vec<(usize, usize)>, sum| Cage {
+            cells,
+            sum: Some(sum),
+        };
+        let killer = Cnf::rules_with(&Variants {
+            cages: vec![cage(vec![(0, 0), (0, 1)], 3), cage(vec![(8, 6), (8, 7), (8, 8)], 5)],
+            ..Variants::default()
+        })
+        .unwrap();
+        assert_eq!(killer.variables, Cnf::VARIABLES + 1);
+        assert!(killer.clauses.contains(&vec![Cnf::VARIABLES as i32 + 1]));
+        assert!(killer.clauses.contains(&Vec::new()));
+    }
+
+    #[test]
+    fn invalid_variants_are_rejected() {
+        let mut regions = [[0; 9]; 9];
+        for (row, regions) in regions.iter_mut().enumerate() {
+            *regions = [row as u8; 9];
+        }
+        assert!(Cnf::rules_with(&Variants {
+            regions: Some(regions),
+            ..Variants::default()
+        })
+        .is_ok());
+
+        regions[0][0] = 1;
+        let variants = Variants {
+            regions: Some(regions),
+            ..Variants::default()
+        };
+        assert!(matches!(Cnf::rules_with(&variants), Err(Error::InvalidVariant(_))));
+        assert!(matches!(
+            Grid::new().to_cnf_with(&variants),
+            Err(Error::InvalidVariant(_))
+        ));
+    }
+}
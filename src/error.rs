@@ -0,0 +1,54 @@
+use std::{fmt, io};
+
+#[derive(Debug)]
+pub enum Error {
+    InvalidLength(usize),
+    InvalidCharacter(char),
+    InvalidValue(u8),
+    NoSolution,
+    MultipleSolutions,
+    InvalidSolution,
+    InvalidEncoding(&'static str),
+    LockedCell(usize, usize),
+    InvalidSave(&'static str),
+    InvalidModel(&'static str),
+    InvalidVariant(&'static str),
+    AtLine(usize, Box<Error>),
+    Io(io::Error),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::InvalidLength(len) => write!(f, "Input must contain 81 cells, found {}", len),
+            Error::InvalidCharacter(chr) => write!(f, "Invalid character '{}' in board string", chr),
+            Error::InvalidValue(value) => write!(f, "Invalid cell value {}, expected 0 to 9", value),
+            Error::NoSolution => write!(f, "Sudoku has no solution"),
+            Error::MultipleSolutions => write!(f, "Sudoku has more than one solution"),
+            Error::InvalidSolution => write!(f, "Grid is not a valid solution"),
+            Error::InvalidEncoding(reason) => write!(f, "Invalid binary encoding: {}", reason),
+            Error::LockedCell(row, col) => write!(f, "Cell ({}, {}) is a locked given", row, col),
+            Error::InvalidSave(reason) => write!(f, "Invalid save file: {}", reason),
+            Error::InvalidModel(reason) => write!(f, "Invalid DIMACS model: {}", reason),
+            Error::InvalidVariant(reason) => write!(f, "Invalid variant rules: {}", reason),
+            Error::AtLine(line, err) => write!(f, "Line {}: {}", line, err),
+            Error::Io(err) => write!(f, "IO error: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::AtLine(_, err) => Some(err.as_ref()),
+            Error::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Self {
+        Error::Io(err)
+    }
+}
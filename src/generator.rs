@@ -0,0 +1,69 @@
+use crate::grid::Grid;
+use crate::rng::Rng;
+
+// Generates random puzzles with a unique solution, the same seed always yields the same puzzles
+#[derive(Debug, Clone)]
+pub struct Generator {
+    rng: Rng,
+    // Remove clues in pairs mirrored through the center so the puzzle keeps 180 degree symmetry
+    pub symmetric: bool,
+}
+
+impl Generator {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            rng: Rng::new(seed),
+            symmetric: false,
+        }
+    }
+
+    pub fn solution(&mut self) -> Grid {
+        // the three boxes on the diagonal do not constrain each other, once they are random the
+        // rest of the grid is filled in by the solution counter
+        let mut grid = Grid::new();
+        for box_idx in [0, 4, 8] {
+            let mut digits = [1, 2, 3, 4, 5, 6, 7, 8, 9];
+            self.rng.shuffle(&mut digits);
+            for (i, &digit) in digits.iter().enumerate() {
+                grid.set(box_idx / 3 * 3 + i / 3, box_idx % 3 * 3 + i % 3, digit);
+            }
+        }
+
+        // relabel so the deterministic completion does not favour small digits anywhere
+        let mut labels = [1, 2, 3, 4, 5, 6, 7, 8, 9];
+        self.rng.shuffle(&mut labels);
+        let solution = grid.first_solution().expect("diagonal boxes can always be completed");
+        Grid::from_cells(solution.cells().map(|value| labels[value as usize - 1]))
+    }
+
+    // Removes clues of a random solution in random order as long as the solution stays unique
+    pub fn puzzle(&mut self) -> Grid {
+        let mut puzzle = self.solution();
+        let mut cells = (0..81).collect::<Vec<usize>>();
+        self.rng.shuffle(&mut cells);
+
+        for cell in cells {
+            let mirror = 80 - cell;
+            if puzzle.cells()[cell] == 0 || (self.symmetric && mirror < cell) {
+                continue;
+            }
+            let mut candidate = puzzle;
+            candidate.set(cell / 9, cell % 9, 0);
+            if self.symmetric {
+                candidate.set(mirror / 9, mirror % 9, 0);
+            }
+            if candidate.has_unique_solution() {
+                puzzle = candidate;
+            }
+        }
+        puzzle
+    }
+}
+
+impl Iterator for Generator {
+    type Item = Grid;
+
+    fn next(&mut self) -> Option<Grid> {
+        Some(self.puzzle())
+    }
+}
@@ -0,0 +1,114 @@
+use crate::error::Error;
+use crate::notation;
+use std::fmt;
+use std::str::FromStr;
+
+const ASCII_ZERO: u8 = 48;
+
+// Plain 9x9 digit grid, 0 marks an empty cell
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Grid {
+    cells: [u8; 81],
+}
+
+impl Grid {
+    pub fn new() -> Self {
+        Self {
+            cells: [0; 81],
+        }
+    }
+
+    pub fn from_cells(cells: [u8; 81]) -> Self {
+        Self {
+            cells,
+        }
+    }
+
+    pub fn cells(&self) -> &[u8; 81] {
+        &self.cells
+    }
+
+    #[inline]
+    pub fn get(&self, row: usize, col: usize) -> u8 {
+        self.cells[row * 9 + col]
+    }
+
+    #[inline]
+    pub fn set(&mut self, row: usize, col: usize, value: u8) {
+        self.cells[row * 9 + col] = value;
+    }
+
+    // from_cells and set take any byte, code that indexes by digit checks this first
+    pub(crate) fn check_values(&self) -> Result<(), Error> {
+        match self.cells.iter().find(|&&value| value > 9) {
+            Some(&value) => Err(Error::InvalidValue(value)),
+            None => Ok(()),
+        }
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.cells.iter().all(|&value| value != 0)
+    }
+
+    // Parses an 81 character line, treating `null_chr`, '0' and '.' as empty cells
+    pub fn parse(input: &str, null_chr: char) -> Result<Self, Error> {
+        let mut cells = [0; 81];
+        let mut len = 0;
+
+        for c in input.chars() {
+            let value = if c == null_chr || c == '0' || c == '.' {
+                0
+            } else {
+                c.to_digit(10).ok_or(Error::InvalidCharacter(c))? as u8
+            };
+            if len < 81 {
+                cells[len] = value;
+            }
+            len += 1;
+        }
+
+        if len != 81 {
+            return Err(Error::InvalidLength(len));
+        }
+
+        Ok(Self {
+            cells,
+        })
+    }
+
+    pub fn to_string_with(&self, null_chr: char) -> String {
+        self.cells
+            .iter()
+            .map(|&value| {
+                if value == 0 {
+                    null_chr
+                } else {
+                    (value + ASCII_ZERO) as char
+                }
+            })
+            .collect()
+    }
+}
+
+impl Default for Grid {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FromStr for Grid {
+    type Err = Error;
+
+    // Auto detects the notation, see notation::parse_text
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        Ok(Self {
+            cells: notation::parse_text(input)?,
+        })
+    }
+}
+
+impl fmt::Display for Grid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.to_string_with('0'))
+    }
+}
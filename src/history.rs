@@ -0,0 +1,110 @@
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Move {
+    pub row: usize,
+    pub col: usize,
+    pub old: u8,
+    pub new: u8,
+}
+
+// Journal of all moves made on a sudoku. Moves before `position` are applied, moves after it
+// have been undone and can be redone until a new move is recorded.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct History {
+    moves: Vec<Move>,
+    position: usize,
+    checkpoints: Vec<(String, usize)>,
+}
+
+impl History {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn moves(&self) -> &[Move] {
+        &self.moves
+    }
+
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    pub fn can_undo(&self) -> bool {
+        self.position > 0
+    }
+
+    pub fn can_redo(&self) -> bool {
+        self.position < self.moves.len()
+    }
+
+    pub fn checkpoints(&self) -> &[(String, usize)] {
+        &self.checkpoints
+    }
+
+    pub fn checkpoint_position(&self, name: &str) -> Option<usize> {
+        self.checkpoints
+            .iter()
+            .rev()
+            .find(|(checkpoint, _)| checkpoint == name)
+            .map(|&(_, position)| position)
+    }
+
+    pub(crate) fn record(&mut self, mv: Move) {
+        self.moves.truncate(self.position);
+        // checkpoints in the discarded future can not be reached anymore
+        self.checkpoints.retain(|&(_, position)| position <= self.position);
+        self.moves.push(mv);
+        self.position += 1;
+    }
+
+    pub(crate) fn add_checkpoint(&mut self, name: &str) {
+        self.checkpoints.retain(|(checkpoint, _)| checkpoint != name);
+        self.checkpoints.push((name.to_string(), self.position));
+    }
+
+    pub(crate) fn step_back(&mut self) -> Option<Move> {
+        if !self.can_undo() {
+            return None;
+        }
+        self.position -= 1;
+        Some(self.moves[self.position])
+    }
+
+    pub(crate) fn step_forward(&mut self) -> Option<Move> {
+        if !self.can_redo() {
+            return None;
+        }
+        self.position += 1;
+        Some(self.moves[self.position - 1])
+    }
+
+    // A journal read from outside has to stay inside its moves and the grid to be replayed
+    #[cfg(feature = "serde")]
+    pub(crate) fn check(&self) -> Result<(), &'static str> {
+        if self.position > self.moves.len() {
+            return Err("history position is past the last move");
+        }
+        if self
+            .moves
+            .iter()
+            .any(|mv| mv.row > 8 || mv.col > 8 || mv.old > 9 || mv.new > 9)
+        {
+            return Err("history move is outside the grid");
+        }
+        if self
+            .checkpoints
+            .iter()
+            .any(|&(_, position)| position > self.moves.len())
+        {
+            return Err("history checkpoint is past the last move");
+        }
+        Ok(())
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.moves.clear();
+        self.position = 0;
+        self.checkpoints.clear();
+    }
+}
@@ -1,7 +1,60 @@
+mod backdoor;
 mod backend;
+mod batch;
+mod binary;
+mod candidates;
+mod canonical;
+mod dimacs;
+mod error;
+mod generator;
+mod grid;
+mod history;
+mod logic;
+mod minimal;
+mod mistakes;
+mod notation;
+#[cfg(feature = "tui")]
+mod play;
+mod reader;
+mod rng;
+#[cfg(feature = "serde")]
+mod serialization;
+mod solutions;
 mod solver;
 mod sudoku;
+mod svg;
+mod symmetry;
+mod transform;
+mod unit;
+mod variants;
 
+pub use backdoor::Backdoors;
 pub use backend::{Backend, BackendType, BitfieldGrid};
-pub use solver::{BruteForceSolver, Solver, WaveFunctionCollapseSolver};
-pub use sudoku::Sudoku;
+#[cfg(feature = "rayon")]
+pub use batch::{par_solve_batch, par_solve_batch_with, par_solve_batch_with_stats};
+pub use batch::{solve_batch, solve_batch_with, solve_batch_with_stats, BatchInput};
+pub use binary::{BinaryReader, BinaryWriter, RecordKind};
+pub use candidates::Candidates;
+pub use canonical::Deduplicator;
+pub use dimacs::Cnf;
+pub use error::Error;
+pub use generator::Generator;
+pub use grid::Grid;
+pub use history::{History, Move};
+pub use logic::{find_step, find_step_with, rate, Hint, HintLevel, Rating, Step, Technique};
+pub use mistakes::Mistakes;
+#[cfg(feature = "tui")]
+pub use play::{play, Game};
+pub use reader::{Puzzle, PuzzleReader};
+#[cfg(feature = "serde")]
+pub use serialization::structured;
+pub use solver::{
+    AnnealingSchedule, AnnealingSolver, BruteForceSolver, CellSelection, Heuristics, SatSolver, Solver, SolverStats,
+    ValueOrder, WaveFunctionCollapseSolver,
+};
+pub use sudoku::{DisplayOptions, Sudoku, SudokuDisplay};
+pub use svg::SvgRenderer;
+pub use symmetry::Symmetry;
+pub use transform::Transform;
+pub use unit::{Conflict, Unit};
+pub use variants::{Cage, Variants};
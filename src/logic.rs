@@ -0,0 +1,636 @@
+use crate::grid::Grid;
+use crate::solutions::UNITS;
+use crate::unit::Unit;
+use crate::Sudoku;
+use std::fmt;
+
+// Human style solving techniques, ordered by difficulty
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Technique {
+    NakedSingle,
+    HiddenSingle,
+    Pointing,
+    Claiming,
+    NakedPair,
+    HiddenPair,
+    NakedTriple,
+    HiddenTriple,
+    XWing,
+}
+
+impl Technique {
+    pub const ALL: [Technique; 9] = [
+        Technique::NakedSingle,
+        Technique::HiddenSingle,
+        Technique::Pointing,
+        Technique::Claiming,
+        Technique::NakedPair,
+        Technique::HiddenPair,
+        Technique::NakedTriple,
+        Technique::HiddenTriple,
+        Technique::XWing,
+    ];
+    pub const SINGLES: [Technique; 2] = [Technique::NakedSingle, Technique::HiddenSingle];
+}
+
+impl fmt::Display for Technique {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Technique::NakedSingle => "Naked Single",
+            Technique::HiddenSingle => "Hidden Single",
+            Technique::Pointing => "Pointing Candidates",
+            Technique::Claiming => "Claiming Candidates",
+            Technique::NakedPair => "Naked Pair",
+            Technique::HiddenPair => "Hidden Pair",
+            Technique::NakedTriple => "Naked Triple",
+            Technique::HiddenTriple => "Hidden Triple",
+            Technique::XWing => "X-Wing",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+// A single logical deduction: either a placement or a set of candidate eliminations
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Step {
+    pub technique: Technique,
+    // The unit the deduction is based on
+    pub unit: Unit,
+    // The cells and digits forming the pattern
+    pub cells: Vec<(usize, usize)>,
+    pub digits: Vec<u8>,
+    pub placement: Option<(usize, usize, u8)>,
+    pub eliminations: Vec<(usize, usize, u8)>,
+}
+
+impl fmt::Display for Step {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} in {}: ", self.technique, self.unit)?;
+        if let Some((row, col, value)) = self.placement {
+            return write!(f, "r{}c{} = {}", row + 1, col + 1, value);
+        }
+        let eliminations = self
+            .eliminations
+            .iter()
+            .map(|(row, col, value)| format!("r{}c{} <> {}", row + 1, col + 1, value))
+            .collect::<Vec<_>>();
+        write!(f, "{}", eliminations.join(", "))
+    }
+}
+
+// Snapshot of the values and candidates the step finder works on
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct LogicState {
+    pub(crate) values: [u8; 81],
+    pub(crate) candidates: [u16; 81],
+}
+
+impl LogicState {
+    pub(crate) fn from_sudoku(sudoku: &Sudoku) -> Self {
+        let mut values = [0; 81];
+        let mut candidates = [0; 81];
+        for i in 0..81 {
+            let (row, col) = (i / 9, i % 9);
+            values[i] = sudoku.get(row, col);
+            if values[i] == 0 {
+                candidates[i] = sudoku.get_candidates(row, col) & sudoku.get_possible_values(row, col);
+            }
+        }
+        Self {
+            values,
+            candidates,
+        }
+    }
+
+    pub(crate) fn from_grid(grid: &Grid) -> Self {
+        let mut state = Self {
+            values: [0; 81],
+            candidates: [0x1FF; 81],
+        };
+        for (cell, &value) in grid.cells().iter().enumerate() {
+            if value != 0 {
+                state.place(cell, value);
+            }
+        }
+        state
+    }
+
+    pub(crate) fn apply(&mut self, step: &Step) {
+        if let Some((row, col, value)) = step.placement {
+            self.place(row * 9 + col, value);
+        }
+        for &(row, col, value) in &step.eliminations {
+            self.candidates[row * 9 + col] &= !(1 << (value - 1));
+        }
+    }
+
+    pub(crate) fn place(&mut self, cell: usize, value: u8) {
+        self.values[cell] = value;
+        self.candidates[cell] = 0;
+        for peer in peers(cell) {
+            self.candidates[peer] &= !(1 << (value - 1));
+        }
+    }
+
+    pub(crate) fn is_solved(&self) -> bool {
+        self.values.iter().all(|&value| value != 0)
+    }
+
+    // Applies steps until none is found, returns whether the puzzle got solved
+    pub(crate) fn solve(&mut self, techniques: &[Technique]) -> bool {
+        let singles_only = techniques
+            .iter()
+            .all(|technique| Technique::SINGLES.contains(technique));
+        if singles_only {
+            self.solve_singles(
+                techniques.contains(&Technique::NakedSingle),
+                techniques.contains(&Technique::HiddenSingle),
+            );
+            return self.is_solved();
+        }
+        while let Some(step) = self.find_step(techniques) {
+            self.apply(&step);
+        }
+        self.is_solved()
+    }
+
+    // Same result as applying naked and / or hidden single steps one by one, without building them
+    fn solve_singles(&mut self, naked: bool, hidden: bool) {
+        let mut progress = naked || hidden;
+        while progress {
+            progress = false;
+            if naked {
+                progress |= self.place_naked_singles();
+            }
+            if !hidden {
+                continue;
+            }
+            for unit in &UNITS {
+                let (mut once, mut twice) = (0u16, 0u16);
+                for &cell in unit {
+                    twice |= once & self.candidates[cell];
+                    once |= self.candidates[cell];
+                }
+                let mut singles = once & !twice;
+                while singles != 0 {
+                    let bit = singles & singles.wrapping_neg();
+                    singles ^= bit;
+                    // an earlier placement in this unit may have removed the candidate meanwhile
+                    if let Some(&cell) = unit.iter().find(|&&cell| self.candidates[cell] & bit != 0) {
+                        self.place(cell, bit.trailing_zeros() as u8 + 1);
+                        progress = true;
+                    }
+                }
+            }
+        }
+    }
+
+    fn place_naked_singles(&mut self) -> bool {
+        let mut placed = false;
+        for cell in 0..81 {
+            if self.values[cell] == 0 && self.candidates[cell].count_ones() == 1 {
+                self.place(cell, self.candidates[cell].trailing_zeros() as u8 + 1);
+                placed = true;
+            }
+        }
+        placed
+    }
+
+    pub(crate) fn find_step(&self, techniques: &[Technique]) -> Option<Step> {
+        techniques.iter().find_map(|&technique| match technique {
+            Technique::NakedSingle => self.naked_single(),
+            Technique::HiddenSingle => self.hidden_single(),
+            Technique::Pointing => self.pointing(),
+            Technique::Claiming => self.claiming(),
+            Technique::NakedPair => self.naked_subset(2),
+            Technique::HiddenPair => self.hidden_subset(2),
+            Technique::NakedTriple => self.naked_subset(3),
+            Technique::HiddenTriple => self.hidden_subset(3),
+            Technique::XWing => self.x_wing(),
+        })
+    }
+
+    fn positions(&self, unit: Unit, value: u8) -> Vec<usize> {
+        unit_cells(unit)
+            .into_iter()
+            .filter(|&cell| self.has(cell, value))
+            .collect()
+    }
+
+    #[inline]
+    fn has(&self, cell: usize, value: u8) -> bool {
+        self.candidates[cell] & 1 << (value - 1) != 0
+    }
+
+    fn naked_single(&self) -> Option<Step> {
+        let cell = (0..81).find(|&cell| self.values[cell] == 0 && self.candidates[cell].count_ones() == 1)?;
+        let value = self.candidates[cell].trailing_zeros() as u8 + 1;
+        Some(Step {
+            technique: Technique::NakedSingle,
+            unit: Unit::Box(cell / 27 * 3 + cell % 9 / 3),
+            cells: vec![coords(cell)],
+            digits: vec![value],
+            placement: Some((cell / 9, cell % 9, value)),
+            eliminations: Vec::new(),
+        })
+    }
+
+    fn hidden_single(&self) -> Option<Step> {
+        // boxes first since that is where people look for them
+        let units = (0..9)
+            .map(Unit::Box)
+            .chain((0..9).map(Unit::Row))
+            .chain((0..9).map(Unit::Col));
+        for unit in units {
+            for value in 1..=9 {
+                let positions = self.positions(unit, value);
+                if positions.len() == 1 {
+                    return Some(Step {
+                        technique: Technique::HiddenSingle,
+                        unit,
+                        cells: vec![coords(positions[0])],
+                        digits: vec![value],
+                        placement: Some((positions[0] / 9, positions[0] % 9, value)),
+                        eliminations: Vec::new(),
+                    });
+                }
+            }
+        }
+        None
+    }
+
+    // A digit confined to one row or column of a box can be removed from the rest of that line
+    fn pointing(&self) -> Option<Step> {
+        for box_idx in 0..9 {
+            for value in 1..=9 {
+                let positions = self.positions(Unit::Box(box_idx), value);
+                if positions.len() < 2 {
+                    continue;
+                }
+                let lines = [Unit::Row(positions[0] / 9), Unit::Col(positions[0] % 9)];
+                for line in lines {
+                    if !positions.iter().all(|&cell| unit_contains(line, cell)) {
+                        continue;
+                    }
+                    let eliminations = self.eliminate(line, value, |cell| !unit_contains(Unit::Box(box_idx), cell));
+                    if !eliminations.is_empty() {
+                        return Some(self.elimination_step(
+                            Technique::Pointing,
+                            Unit::Box(box_idx),
+                            &positions,
+                            vec![value],
+                            eliminations,
+                        ));
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    // A digit confined to one box within a row or column can be removed from the rest of that box
+    fn claiming(&self) -> Option<Step> {
+        let lines = (0..9).map(Unit::Row).chain((0..9).map(Unit::Col));
+        for line in lines {
+            for value in 1..=9 {
+                let positions = self.positions(line, value);
+                if positions.len() < 2 {
+                    continue;
+                }
+                let box_unit = Unit::Box(positions[0] / 27 * 3 + positions[0] % 9 / 3);
+                if !positions.iter().all(|&cell| unit_contains(box_unit, cell)) {
+                    continue;
+                }
+                let eliminations = self.eliminate(box_unit, value, |cell| !unit_contains(line, cell));
+                if !eliminations.is_empty() {
+                    return Some(self.elimination_step(
+                        Technique::Claiming,
+                        line,
+                        &positions,
+                        vec![value],
+                        eliminations,
+                    ));
+                }
+            }
+        }
+        None
+    }
+
+    // n cells of a unit sharing exactly n candidates, these can be removed from the other cells
+    fn naked_subset(&self, n: usize) -> Option<Step> {
+        let technique = if n == 2 {
+            Technique::NakedPair
+        } else {
+            Technique::NakedTriple
+        };
+        for unit in Unit::all() {
+            let cells = unit_cells(unit)
+                .into_iter()
+                .filter(|&cell| self.values[cell] == 0 && (2..=n as u32).contains(&self.candidates[cell].count_ones()))
+                .collect::<Vec<_>>();
+            for subset in combinations(&cells, n) {
+                let mask = subset.iter().fold(0, |mask, &cell| mask | self.candidates[cell]);
+                if mask.count_ones() as usize != n {
+                    continue;
+                }
+                let digits = mask_digits(mask);
+                let eliminations = digits
+                    .iter()
+                    .flat_map(|&value| self.eliminate(unit, value, |cell| !subset.contains(&cell)))
+                    .collect::<Vec<_>>();
+                if !eliminations.is_empty() {
+                    return Some(self.elimination_step(technique, unit, &subset, digits, eliminations));
+                }
+            }
+        }
+        None
+    }
+
+    // n digits of a unit confined to the same n cells, all other candidates of those cells go
+    fn hidden_subset(&self, n: usize) -> Option<Step> {
+        let technique = if n == 2 {
+            Technique::HiddenPair
+        } else {
+            Technique::HiddenTriple
+        };
+        for unit in Unit::all() {
+            let digits = (1..=9)
+                .filter(|&value| (2..=n).contains(&self.positions(unit, value).len()))
+                .collect::<Vec<u8>>();
+            for subset in combinations(&digits, n) {
+                let mut cells = subset
+                    .iter()
+                    .flat_map(|&value| self.positions(unit, value))
+                    .collect::<Vec<_>>();
+                cells.sort_unstable();
+                cells.dedup();
+                if cells.len() != n {
+                    continue;
+                }
+                let mask = subset.iter().fold(0u16, |mask, &value| mask | 1 << (value - 1));
+                let eliminations = cells
+                    .iter()
+                    .flat_map(|&cell| {
+                        mask_digits(self.candidates[cell] & !mask)
+                            .into_iter()
+                            .map(move |value| (cell / 9, cell % 9, value))
+                    })
+                    .collect::<Vec<_>>();
+                if !eliminations.is_empty() {
+                    return Some(self.elimination_step(technique, unit, &cells, subset, eliminations));
+                }
+            }
+        }
+        None
+    }
+
+    // A digit limited to the same two columns in two rows can be removed from the rest of those
+    // columns (and the same with rows and columns swapped)
+    fn x_wing(&self) -> Option<Step> {
+        for value in 1..=9 {
+            for by_row in [true, false] {
+                let line = |i| if by_row { Unit::Row(i) } else { Unit::Col(i) };
+                let cross = |i| if by_row { Unit::Col(i) } else { Unit::Row(i) };
+                let offset = |cell: usize| if by_row { cell % 9 } else { cell / 9 };
+
+                let bases = (0..9)
+                    .map(|i| (i, self.positions(line(i), value)))
+                    .filter(|(_, positions)| positions.len() == 2)
+                    .collect::<Vec<_>>();
+                for (a, (first, first_positions)) in bases.iter().enumerate() {
+                    for (_, second_positions) in &bases[a + 1..] {
+                        let covers = [offset(first_positions[0]), offset(first_positions[1])];
+                        if covers != [offset(second_positions[0]), offset(second_positions[1])] {
+                            continue;
+                        }
+                        let pattern = [first_positions.clone(), second_positions.clone()].concat();
+                        let eliminations = covers
+                            .iter()
+                            .flat_map(|&cover| self.eliminate(cross(cover), value, |cell| !pattern.contains(&cell)))
+                            .collect::<Vec<_>>();
+                        if !eliminations.is_empty() {
+                            return Some(self.elimination_step(
+                                Technique::XWing,
+                                line(*first),
+                                &pattern,
+                                vec![value],
+                                eliminations,
+                            ));
+                        }
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    // Candidate eliminations of value in the unit, restricted to cells passing the filter
+    fn eliminate(&self, unit: Unit, value: u8, filter: impl Fn(usize) -> bool) -> Vec<(usize, usize, u8)> {
+        unit_cells(unit)
+            .into_iter()
+            .filter(|&cell| filter(cell) && self.has(cell, value))
+            .map(|cell| (cell / 9, cell % 9, value))
+            .collect()
+    }
+
+    fn elimination_step(
+        &self,
+        technique: Technique,
+        unit: Unit,
+        cells: &[usize],
+        digits: Vec<u8>,
+        eliminations: Vec<(usize, usize, u8)>,
+    ) -> Step {
+        Step {
+            technique,
+            unit,
+            cells: cells.iter().map(|&cell| coords(cell)).collect(),
+            digits,
+            placement: None,
+            eliminations,
+        }
+    }
+}
+
+#[inline]
+fn coords(cell: usize) -> (usize, usize) {
+    (cell / 9, cell % 9)
+}
+
+#[inline]
+fn unit_cells(unit: Unit) -> [usize; 9] {
+    unit.cells().map(|(row, col)| row * 9 + col)
+}
+
+#[inline]
+fn unit_contains(unit: Unit, cell: usize) -> bool {
+    unit.contains(cell / 9, cell % 9)
+}
+
+fn peers(cell: usize) -> impl Iterator<Item = usize> {
+    let (row, col) = coords(cell);
+    let box_row = row / 3 * 3;
+    let box_col = col / 3 * 3;
+    (0..9)
+        .flat_map(move |i| [row * 9 + i, i * 9 + col, (box_row + i / 3) * 9 + box_col + i % 3])
+        .filter(move |&peer| peer != cell)
+}
+
+fn mask_digits(mask: u16) -> Vec<u8> {
+    (1..=9).filter(|&value| mask & 1 << (value - 1) != 0).collect()
+}
+
+// Every subset of n items in lexicographic order, built one at a time so large inputs only cost
+// time and not memory
+pub(crate) fn combinations<T: Copy>(items: &[T], n: usize) -> Combinations<'_, T> {
+    Combinations {
+        items,
+        indices: (0..n).collect(),
+        done: n > items.len(),
+    }
+}
+
+pub(crate) struct Combinations<'a, T> {
+    items: &'a [T],
+    indices: Vec<usize>,
+    done: bool,
+}
+
+impl<T: Copy> Iterator for Combinations<'_, T> {
+    type Item = Vec<T>;
+
+    fn next(&mut self) -> Option<Vec<T>> {
+        if self.done {
+            return None;
+        }
+        let current = self.indices.iter().map(|&i| self.items[i]).collect();
+        // advance the rightmost index that still has room, the ones after it follow directly
+        let (n, len) = (self.indices.len(), self.items.len());
+        match (0..n).rev().find(|&k| self.indices[k] < len - n + k) {
+            Some(k) => {
+                self.indices[k] += 1;
+                for j in k + 1..n {
+                    self.indices[j] = self.indices[j - 1] + 1;
+                }
+            }
+            None => self.done = true,
+        }
+        Some(current)
+    }
+}
+
+// Finds the easiest logical step that can be applied to the sudoku
+pub fn find_step(sudoku: &Sudoku) -> Option<Step> {
+    find_step_with(sudoku, &Technique::ALL)
+}
+
+pub fn find_step_with(sudoku: &Sudoku, techniques: &[Technique]) -> Option<Step> {
+    LogicState::from_sudoku(sudoku).find_step(techniques)
+}
+
+// Difficulty of a puzzle for a human solver
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Rating {
+    // The hardest technique the step finder needed
+    pub hardest: Option<Technique>,
+    pub steps: usize,
+    // false if the techniques ran out and the rest of the puzzle needs guessing
+    pub solved: bool,
+}
+
+pub fn rate(sudoku: &Sudoku) -> Rating {
+    let mut state = LogicState::from_sudoku(sudoku);
+    let mut rating = Rating {
+        hardest: None,
+        steps: 0,
+        solved: false,
+    };
+    while let Some(step) = state.find_step(&Technique::ALL) {
+        rating.hardest = rating.hardest.max(Some(step.technique));
+        rating.steps += 1;
+        state.apply(&step);
+    }
+    rating.solved = state.is_solved();
+    rating
+}
+
+impl fmt::Display for Rating {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.solved, self.hardest) {
+            (false, _) => write!(f, "Guessing"),
+            (true, Some(technique)) => write!(f, "{}", technique),
+            (true, None) => write!(f, "Solved"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum HintLevel {
+    Region,
+    Technique,
+    Pattern,
+    Step,
+}
+
+// Hints of increasing detail, every level includes the information of the previous ones
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Hint {
+    Region(Unit),
+    Technique(Unit, Technique),
+    Pattern {
+        unit: Unit,
+        technique: Technique,
+        cells: Vec<(usize, usize)>,
+        digits: Vec<u8>,
+    },
+    Step(Step),
+}
+
+impl Hint {
+    pub fn from_step(step: Step, level: HintLevel) -> Self {
+        match level {
+            HintLevel::Region => Hint::Region(step.unit),
+            HintLevel::Technique => Hint::Technique(step.unit, step.technique),
+            HintLevel::Pattern => Hint::Pattern {
+                unit: step.unit,
+                technique: step.technique,
+                cells: step.cells,
+                digits: step.digits,
+            },
+            HintLevel::Step => Hint::Step(step),
+        }
+    }
+}
+
+impl fmt::Display for Hint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Hint::Region(unit) => write!(f, "Take a look at {}", unit),
+            Hint::Technique(unit, technique) => write!(f, "There is a {} in {}", technique, unit),
+            Hint::Pattern {
+                unit,
+                technique,
+                cells,
+                digits,
+            } => {
+                let cells = cells
+                    .iter()
+                    .map(|(row, col)| format!("r{}c{}", row + 1, col + 1))
+                    .collect::<Vec<_>>();
+                let digits = digits.iter().map(|digit| digit.to_string()).collect::<Vec<_>>();
+                write!(
+                    f,
+                    "There is a {} in {} on {} with {}",
+                    technique,
+                    unit,
+                    cells.join(", "),
+                    digits.join(", ")
+                )
+            }
+            Hint::Step(step) => write!(f, "{}", step),
+        }
+    }
+}
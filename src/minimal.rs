@@ -0,0 +1,57 @@
+use crate::error::Error;
+use crate::grid::Grid;
+use crate::symmetry::Symmetry;
+
+impl Grid {
+    // A puzzle is minimal if it has a unique solution and removing any clue breaks that
+    pub fn is_minimal(&self) -> bool {
+        matches!(self.redundant_clues(), Ok(clues) if clues.is_empty())
+    }
+
+    // The clues that can be removed one at a time without losing uniqueness. Removing several of
+    // them at once might still break it.
+    pub fn redundant_clues(&self) -> Result<Vec<(usize, usize)>, Error> {
+        self.try_unique_solution()?;
+        let mut redundant = Vec::new();
+        for row in 0..9 {
+            for col in 0..9 {
+                if self.get(row, col) == 0 {
+                    continue;
+                }
+                let mut reduced = *self;
+                reduced.set(row, col, 0);
+                if reduced.has_unique_solution() {
+                    redundant.push((row, col));
+                }
+            }
+        }
+        Ok(redundant)
+    }
+
+    // Removes clues in reading order as long as the solution stays unique. With a symmetry all
+    // clues of an orbit are removed together, the result keeps the symmetry of a symmetric input
+    // but is only minimal with respect to removing whole orbits.
+    pub fn minimize(&self, symmetry: Option<Symmetry>) -> Result<Grid, Error> {
+        self.try_unique_solution()?;
+        let mut puzzle = *self;
+        for row in 0..9 {
+            for col in 0..9 {
+                if puzzle.get(row, col) == 0 {
+                    continue;
+                }
+                let cells = match symmetry {
+                    Some(symmetry) => symmetry.orbit(row, col),
+                    None => vec![(row, col)],
+                };
+                let mut reduced = puzzle;
+                for &(r, c) in &cells {
+                    reduced.set(r, c, 0);
+                }
+                if reduced.has_unique_solution() {
+                    puzzle = reduced;
+                }
+            }
+        }
+        Ok(puzzle)
+    }
+}
@@ -0,0 +1,59 @@
+use crate::error::Error;
+use crate::grid::Grid;
+use crate::Sudoku;
+
+// Player placed digits that disagree with the unique solution of the givens
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Mistakes {
+    pub wrong: Vec<(usize, usize, u8)>,
+    // The wrong digits that do not clash with any other digit yet. is_valid can not see these but
+    // every one of them makes the puzzle unsolvable.
+    pub dead_ends: Vec<(usize, usize, u8)>,
+}
+
+impl Mistakes {
+    pub fn is_empty(&self) -> bool {
+        self.wrong.is_empty()
+    }
+}
+
+impl Sudoku {
+    // The solution of the givens, fails if the puzzle has none or several solutions
+    pub fn solution(&self) -> Result<Grid, Error> {
+        self.get_givens().try_unique_solution()
+    }
+
+    pub fn count_solutions(&self, limit: usize) -> usize {
+        self.to_grid().count_solutions(limit)
+    }
+
+    // Whether the digits placed so far can still be completed to a solution
+    pub fn is_solvable(&self) -> bool {
+        self.to_grid().is_solvable()
+    }
+
+    pub fn find_mistakes(&self) -> Result<Mistakes, Error> {
+        let solution = self.solution()?;
+        let conflicting = self
+            .get_conflicts()
+            .iter()
+            .flat_map(|conflict| [conflict.first, conflict.second])
+            .collect::<Vec<_>>();
+
+        let mut mistakes = Mistakes::default();
+        for row in 0..9 {
+            for col in 0..9 {
+                let value = self.get(row, col);
+                if value == 0 || value == solution.get(row, col) {
+                    continue;
+                }
+                mistakes.wrong.push((row, col, value));
+                if !conflicting.contains(&(row, col)) {
+                    mistakes.dead_ends.push((row, col, value));
+                }
+            }
+        }
+        Ok(mistakes)
+    }
+}
@@ -0,0 +1,228 @@
+use crate::error::Error;
+
+// Characters that draw horizontal rules or junctions, lines made only of these are skipped
+const RULE_CHRS: &[char] = &['-', '+', '='];
+// Characters that separate cells inside a row
+const VERTICAL_CHRS: &[char] = &['|', '!', ':', '║', '│', '┃', '┆', '┊'];
+const BLANK_CHRS: &[char] = &['.', '0', '_'];
+
+// Parses grid text in any of the supported notations and returns the cells in row major order.
+//
+// Two strategies are tried in order:
+// 1. Whitespace and separators are ignored, the remaining characters must be exactly 81 cells.
+//    This covers single line puzzles, 9 lines of 9 and grids with separators.
+// 2. Spaces count as blanks. Rule lines are skipped and every remaining line must describe a
+//    full row, either as 9 characters or as 9 separated cells (like the output of Display).
+pub(crate) fn parse_text(input: &str) -> Result<[u8; 81], Error> {
+    let mut cells = [0; 81];
+    let mut len = 0;
+
+    for c in input.chars() {
+        if c.is_whitespace() || is_separator(c) {
+            continue;
+        }
+        if len < 81 {
+            cells[len] = cell_value(c)?;
+        }
+        len += 1;
+    }
+
+    if len == 81 {
+        return Ok(cells);
+    }
+
+    parse_rows(input).ok_or(Error::InvalidLength(len))
+}
+
+fn parse_rows(input: &str) -> Option<[u8; 81]> {
+    let mut cells = [0; 81];
+    let mut rows = 0;
+
+    for line in input.lines() {
+        let line = line.trim_end_matches('\r');
+        let blank_row = line.chars().count() == 9 && line.chars().all(|c| c == ' ');
+        if !blank_row && line.chars().all(|c| c.is_whitespace() || is_rule(c)) {
+            continue;
+        }
+        if rows == 9 {
+            return None;
+        }
+        cells[rows * 9..rows * 9 + 9].copy_from_slice(&parse_row(line)?);
+        rows += 1;
+    }
+
+    (rows == 9).then_some(cells)
+}
+
+fn parse_row(line: &str) -> Option<[u8; 9]> {
+    let mut row = [0; 9];
+
+    // Whitespace outside of the outer separators is indentation and not part of the row
+    let mut line = line;
+    if line.trim_start().starts_with(is_separator) {
+        line = line.trim_start();
+    }
+    if line.trim_end().ends_with(is_separator) {
+        line = line.trim_end();
+    }
+
+    // Display like rows: every cell is enclosed by separators
+    let segments = line
+        .split(|c| VERTICAL_CHRS.contains(&c))
+        .filter(|segment| !segment.is_empty())
+        .collect::<Vec<_>>();
+    if segments.len() == 9 && segments.iter().all(|segment| segment.trim().chars().count() <= 1) {
+        for (cell, segment) in row.iter_mut().zip(segments) {
+            *cell = match segment.trim().chars().next() {
+                Some(c) => cell_value(c).ok()?,
+                None => 0,
+            };
+        }
+        return Some(row);
+    }
+
+    // Plain rows: every character is a cell and spaces are blanks, missing cells at the end of
+    // a row are blanks as well
+    let chars = line.chars().filter(|&c| !is_separator(c)).collect::<Vec<_>>();
+    let mut chars = &chars[..];
+    if chars.len() > 9 {
+        let end = chars.iter().rposition(|c| !c.is_whitespace()).map_or(0, |i| i + 1);
+        chars = &chars[..end];
+    }
+    if chars.len() > 9 {
+        let start = chars.iter().position(|c| !c.is_whitespace()).unwrap_or(0);
+        chars = &chars[start..];
+    }
+    if chars.len() > 9 {
+        return None;
+    }
+    for (cell, &c) in row.iter_mut().zip(chars) {
+        *cell = if c == ' ' { 0 } else { cell_value(c).ok()? };
+    }
+    Some(row)
+}
+
+#[inline]
+fn cell_value(c: char) -> Result<u8, Error> {
+    if BLANK_CHRS.contains(&c) {
+        return Ok(0);
+    }
+    match c.to_digit(10) {
+        Some(value) => Ok(value as u8),
+        None => Err(Error::InvalidCharacter(c)),
+    }
+}
+
+#[inline]
+fn is_separator(c: char) -> bool {
+    is_rule(c) || VERTICAL_CHRS.contains(&c)
+}
+
+// Box drawing characters (U+2500 - U+257F) are treated as rules unless they are vertical lines
+#[inline]
+fn is_rule(c: char) -> bool {
+    RULE_CHRS.contains(&c) || (('\u{2500}'..='\u{257F}').contains(&c) && !VERTICAL_CHRS.contains(&c))
+}
+
+// Parses a pencil-mark grid (as exported by HoDoKu or SudokuWiki). Every cell is a group of
+// digits, cells are separated by whitespace or grid lines. A single digit is a placed value and
+// a lone 0 marks a cell without candidates.
+pub(crate) fn parse_pencil_marks(input: &str) -> Result<[u16; 81], Error> {
+    let mut cells = [0; 81];
+    let mut len = 0;
+
+    for token in input.split(|c: char| c.is_whitespace() || is_separator(c) || c == '.' || c == '\'') {
+        if token.is_empty() {
+            continue;
+        }
+        let mut mask = 0;
+        if token != "0" {
+            for c in token.chars() {
+                match c.to_digit(10) {
+                    Some(value) if value != 0 => mask |= 1 << (value - 1),
+                    _ => return Err(Error::InvalidCharacter(c)),
+                }
+            }
+        }
+        if len < 81 {
+            cells[len] = mask;
+        }
+        len += 1;
+    }
+
+    if len != 81 {
+        return Err(Error::InvalidLength(len));
+    }
+    Ok(cells)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::candidates::Candidates;
+    use crate::grid::Grid;
+    use crate::sudoku::Sudoku;
+
+    const PUZZLE: &str = "530070000600195000098000060800060003400803001700020006060000280000419005000080079";
+
+    #[test]
+    fn every_notation_reads_the_same_cells() {
+        let expected = parse_text(PUZZLE).unwrap();
+        let dotted = PUZZLE.replace('0', ".");
+        let lines = PUZZLE
+            .as_bytes()
+            .chunks(9)
+            .map(|row| std::str::from_utf8(row).unwrap())
+            .collect::<Vec<_>>()
+            .join("\n");
+        let spaced = lines.replace('0', " ");
+        let separated = "\
+            53. | .7. | ...\n\
+            6.. | 195 | ...\n\
+            .98 | ... | .6.\n\
+            ----+-----+----\n\
+            8.. | .6. | ..3\n\
+            4.. | 8.3 | ..1\n\
+            7.. | .2. | ..6\n\
+            ----+-----+----\n\
+            .6. | ... | 28.\n\
+            ... | 419 | ..5\n\
+            ... | .8. | .79";
+
+        for text in [dotted.as_str(), &lines, &spaced, separated] {
+            assert_eq!(parse_text(text).unwrap(), expected, "{}", text);
+        }
+    }
+
+    #[test]
+    fn display_output_round_trips() {
+        let sudoku = PUZZLE.parse::<Sudoku>().unwrap();
+        let reparsed = sudoku.to_string().parse::<Grid>().unwrap();
+        assert_eq!(reparsed, sudoku.to_grid());
+        assert_eq!(reparsed.to_string(), PUZZLE);
+    }
+
+    #[test]
+    fn pencil_marks_round_trip() {
+        let sudoku = PUZZLE.parse::<Sudoku>().unwrap();
+        let candidates = Candidates::from_sudoku(&sudoku);
+        let reparsed = candidates.to_string().parse::<Candidates>().unwrap();
+        assert_eq!(reparsed, candidates);
+    }
+
+    #[test]
+    fn malformed_text_is_rejected() {
+        assert!(matches!(parse_text(&PUZZLE[..80]), Err(Error::InvalidLength(80))));
+        assert!(matches!(
+            parse_text(&format!("{}1", PUZZLE)),
+            Err(Error::InvalidLength(82))
+        ));
+        assert!(matches!(
+            parse_text(&PUZZLE.replacen('0', "x", 1)),
+            Err(Error::InvalidCharacter('x'))
+        ));
+        assert!(matches!(parse_pencil_marks("123 45"), Err(Error::InvalidLength(2))));
+        let marks = format!("{}{}", "1 ".repeat(80), "1x");
+        assert!(matches!(parse_pencil_marks(&marks), Err(Error::InvalidCharacter('x'))));
+    }
+}
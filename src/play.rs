@@ -0,0 +1,323 @@
+use crate::candidates::Candidates;
+use crate::error::Error;
+use crate::grid::Grid;
+use crate::logic::HintLevel;
+use crate::sudoku::DisplayOptions;
+use crate::unit::Unit;
+use crate::Sudoku;
+use crossterm::cursor::{Hide, MoveTo, Show};
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use crossterm::terminal::{self, Clear, ClearType, EnterAlternateScreen, LeaveAlternateScreen};
+use crossterm::{execute, queue};
+use std::io::{self, BufRead, Write};
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+const SAVE_HEADER: &str = "suji save 1";
+const HELP: &str = "arrows/hjkl move  1-9 enter  0/del clear  p pencil  u/r undo/redo  ? hint  m check  s save  q quit";
+
+// A game in progress, everything needed to resume it later
+#[derive(Debug, Clone)]
+pub struct Game {
+    pub sudoku: Sudoku,
+    // The player's own pencil marks, independent of the candidates stored on the sudoku. They are
+    // not part of the move history, so undo does not bring back marks cleared by a placed digit.
+    pub marks: Candidates,
+    pub elapsed: Duration,
+}
+
+impl Game {
+    pub fn new(puzzle: &Grid) -> Self {
+        let mut sudoku = Sudoku::from(*puzzle);
+        sudoku.lock_givens(true);
+        sudoku.enable_history();
+        Self {
+            sudoku,
+            marks: Candidates::from_masks([0; 81]),
+            elapsed: Duration::ZERO,
+        }
+    }
+
+    // Line based text format: a header followed by `key value` lines
+    pub fn save(&self, mut writer: impl Write) -> io::Result<()> {
+        let marks = (0..81).map(|i| self.marks.cell_string(i)).collect::<Vec<_>>();
+        writeln!(writer, "{}", SAVE_HEADER)?;
+        writeln!(writer, "givens {}", self.sudoku.get_givens())?;
+        writeln!(writer, "cells {}", self.sudoku.to_grid())?;
+        writeln!(writer, "marks {}", marks.join(" "))?;
+        writeln!(writer, "elapsed {}", self.elapsed.as_secs())
+    }
+
+    pub fn load(reader: impl BufRead) -> Result<Self, Error> {
+        let mut lines = reader.lines();
+        if lines.next().transpose()?.as_deref() != Some(SAVE_HEADER) {
+            return Err(Error::InvalidSave("missing save header"));
+        }
+
+        let (mut givens, mut cells, mut marks, mut elapsed) = (None, None, None, None);
+        for line in lines {
+            let line = line?;
+            let (key, value) = line.split_once(' ').unwrap_or((line.as_str(), ""));
+            match key {
+                "givens" => givens = Some(value.parse::<Grid>()?),
+                "cells" => cells = Some(value.parse::<Grid>()?),
+                "marks" => marks = Some(value.parse::<Candidates>()?),
+                "elapsed" => elapsed = value.parse().ok().map(Duration::from_secs),
+                _ => {}
+            }
+        }
+
+        let givens = givens.ok_or(Error::InvalidSave("missing givens"))?;
+        let cells = cells.ok_or(Error::InvalidSave("missing cells"))?;
+        let mut game = Self::new(&givens);
+        for (i, &value) in cells.cells().iter().enumerate() {
+            if !game.sudoku.is_given(i / 9, i % 9) {
+                game.sudoku.set(i / 9, i % 9, value);
+            }
+        }
+        // restoring the cells is not something the player should be able to undo
+        game.sudoku.disable_history();
+        game.sudoku.enable_history();
+        game.marks = marks.unwrap_or(game.marks);
+        game.elapsed = elapsed.unwrap_or_default();
+        Ok(game)
+    }
+
+    pub fn save_to(&self, path: &Path) -> io::Result<()> {
+        self.save(io::BufWriter::new(std::fs::File::create(path)?))
+    }
+
+    pub fn load_from(path: &Path) -> Result<Self, Error> {
+        Self::load(io::BufReader::new(std::fs::File::open(path)?))
+    }
+}
+
+// State of the terminal UI that is not part of the game itself
+struct Player<'a> {
+    game: &'a mut Game,
+    save_path: &'a Path,
+    cursor: (usize, usize),
+    pencil: bool,
+    hint_level: Option<HintLevel>,
+    message: String,
+    // start of the running part of this session, None while the clock is stopped
+    started: Option<Instant>,
+}
+
+impl Player<'_> {
+    fn elapsed(&self) -> Duration {
+        self.game.elapsed + self.started.map_or(Duration::ZERO, |started| started.elapsed())
+    }
+
+    // Moves the time of this session into the game, e.g. before saving
+    fn sync_time(&mut self) {
+        if let Some(started) = &mut self.started {
+            self.game.elapsed += started.elapsed();
+            *started = Instant::now();
+        }
+    }
+
+    // The clock only runs while the puzzle is unsolved, undoing the last digit restarts it
+    fn update_clock(&mut self) {
+        let solved = self.game.sudoku.is_solved();
+        if solved && self.started.is_some() {
+            self.sync_time();
+            self.started = None;
+        } else if !solved && self.started.is_none() {
+            self.started = Some(Instant::now());
+        }
+    }
+
+    fn draw(&self, out: &mut impl Write) -> io::Result<()> {
+        let show_marks = self.game.marks.masks().iter().any(|&mask| mask != 0);
+        let options = DisplayOptions {
+            color: true,
+            givens: None,
+            highlight_conflicts: true,
+            candidates: show_marks.then_some(self.game.marks),
+        };
+
+        queue!(out, Hide, MoveTo(0, 0), Clear(ClearType::All))?;
+        for line in self.game.sudoku.display(options).to_string().lines() {
+            write!(out, "{}\r\n", line)?;
+        }
+
+        let seconds = self.elapsed().as_secs();
+        let mode = if self.pencil { "pencil" } else { "digits" };
+        write!(out, "\r\n {:02}:{:02}  mode: {}\r\n", seconds / 60, seconds % 60, mode)?;
+        write!(out, " {}\r\n\r\n {}\r\n", self.message, HELP)?;
+
+        // the terminal cursor marks the selected cell
+        let lines = if show_marks { 3 } else { 1 };
+        let (row, col) = self.cursor;
+        let y = 1 + row * (lines + 1) + lines / 2;
+        let x = 2 + col * 4;
+        queue!(out, MoveTo(x as u16, y as u16), Show)?;
+        out.flush()
+    }
+
+    fn enter(&mut self, value: u8) {
+        let (row, col) = self.cursor;
+        if self.pencil {
+            if self.game.sudoku.get(row, col) != 0 {
+                return;
+            }
+            if self.game.marks.contains(row, col, value) {
+                self.game.marks.remove(row, col, value);
+            } else {
+                self.game.marks.insert(row, col, value);
+            }
+            return;
+        }
+
+        if self.game.sudoku.try_set(row, col, value).is_err() {
+            self.message = "That cell is a given".to_string();
+            return;
+        }
+        // placing a digit clears it from the pencil marks it can see, undoing the digit keeps
+        // them cleared
+        for unit in [Unit::Row(row), Unit::Col(col), Unit::Box(row / 3 * 3 + col / 3)] {
+            for (r, c) in unit.cells() {
+                self.game.marks.remove(r, c, value);
+            }
+        }
+        self.update_clock();
+        if self.game.sudoku.is_solved() {
+            let seconds = self.game.elapsed.as_secs();
+            self.message = format!("Solved in {:02}:{:02}!", seconds / 60, seconds % 60);
+        }
+    }
+
+    fn clear(&mut self) {
+        let (row, col) = self.cursor;
+        if self.pencil || self.game.sudoku.get(row, col) == 0 {
+            self.game.marks.set(row, col, 0);
+        } else if self.game.sudoku.try_unset(row, col).is_err() {
+            self.message = "That cell is a given".to_string();
+        }
+    }
+
+    // Every press of the hint key reveals more detail about the same step
+    fn hint(&mut self) {
+        if let Ok(mistakes) = self.game.sudoku.find_mistakes() {
+            if let Some(&(row, col, _)) = mistakes.wrong.first() {
+                self.message = format!("r{}c{} is wrong", row + 1, col + 1);
+                return;
+            }
+        }
+        let level = match self.hint_level {
+            None => HintLevel::Region,
+            Some(HintLevel::Region) => HintLevel::Technique,
+            Some(HintLevel::Technique) => HintLevel::Pattern,
+            Some(_) => HintLevel::Step,
+        };
+        self.hint_level = Some(level);
+        self.message = match self.game.sudoku.hint(level) {
+            Some(hint) => hint.to_string(),
+            None => "No hint available".to_string(),
+        };
+    }
+
+    fn check(&mut self) {
+        self.message = match self.game.sudoku.find_mistakes() {
+            Ok(mistakes) if mistakes.is_empty() => "No mistakes so far".to_string(),
+            Ok(mistakes) => format!("{} wrong digit(s)", mistakes.wrong.len()),
+            Err(err) => err.to_string(),
+        };
+    }
+
+    fn save(&mut self) {
+        self.sync_time();
+        self.message = match self.game.save_to(self.save_path) {
+            Ok(()) => format!("Saved to {}", self.save_path.display()),
+            Err(err) => format!("Saving failed: {}", err),
+        };
+    }
+
+    // Returns false once the player quits
+    fn handle(&mut self, key: KeyEvent) -> bool {
+        let (row, col) = self.cursor;
+        if !matches!(key.code, KeyCode::Char('?')) {
+            self.hint_level = None;
+        }
+        self.message.clear();
+
+        match key.code {
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => return false,
+            KeyCode::Char('q') | KeyCode::Esc => return false,
+            KeyCode::Up | KeyCode::Char('k') => self.cursor = ((row + 8) % 9, col),
+            KeyCode::Down | KeyCode::Char('j') => self.cursor = ((row + 1) % 9, col),
+            KeyCode::Left | KeyCode::Char('h') => self.cursor = (row, (col + 8) % 9),
+            KeyCode::Right | KeyCode::Char('l') => self.cursor = (row, (col + 1) % 9),
+            KeyCode::Char(chr @ '1'..='9') => self.enter(chr as u8 - b'0'),
+            KeyCode::Char('0' | '.' | ' ') | KeyCode::Backspace | KeyCode::Delete => self.clear(),
+            KeyCode::Char('p') => self.pencil = !self.pencil,
+            KeyCode::Char('u') => {
+                self.game.sudoku.undo();
+            }
+            KeyCode::Char('r') => {
+                self.game.sudoku.redo();
+            }
+            KeyCode::Char('?') => self.hint(),
+            KeyCode::Char('m') => self.check(),
+            KeyCode::Char('s') => self.save(),
+            _ => {}
+        }
+        self.update_clock();
+        true
+    }
+
+    fn run(&mut self, out: &mut impl Write) -> io::Result<()> {
+        loop {
+            self.draw(out)?;
+            // wake up every second so the timer keeps running
+            if !event::poll(Duration::from_secs(1))? {
+                continue;
+            }
+            if let Event::Key(key) = event::read()? {
+                if key.kind != KeyEventKind::Release && !self.handle(key) {
+                    self.sync_time();
+                    return Ok(());
+                }
+            }
+        }
+    }
+}
+
+// Raw mode and the alternate screen for as long as it lives. Dropping it gives the terminal
+// back, also when the player panics.
+struct TerminalGuard;
+
+impl TerminalGuard {
+    fn enter(out: &mut impl Write) -> io::Result<Self> {
+        terminal::enable_raw_mode()?;
+        let guard = TerminalGuard;
+        execute!(out, EnterAlternateScreen)?;
+        Ok(guard)
+    }
+}
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        let _ = execute!(io::stdout(), Show, LeaveAlternateScreen);
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+// Runs the interactive player until the player quits, `s` saves the game to save_path
+pub fn play(game: &mut Game, save_path: &Path) -> io::Result<()> {
+    let mut out = io::stdout();
+    let _terminal = TerminalGuard::enter(&mut out)?;
+
+    let mut player = Player {
+        game,
+        save_path,
+        cursor: (0, 0),
+        pencil: false,
+        hint_level: None,
+        message: String::new(),
+        started: None,
+    };
+    player.update_clock();
+    player.run(&mut out)
+}
@@ -0,0 +1,177 @@
+use crate::error::Error;
+use crate::grid::Grid;
+use std::io::BufRead;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Puzzle {
+    pub grid: Grid,
+    // Everything following the 81 cells on the same line (ratings, names, ...)
+    pub metadata: String,
+}
+
+// Streams puzzles line by line from any BufRead.
+//
+// Supported layout:
+// - an optional count header as the first line (as in data/bench.txt), a number of at most 10
+//   digits. Only the first line can be a header, later short lines are broken puzzle lines.
+// - empty lines and comment lines starting with '#' are skipped
+// - every other line starts with 81 cells where '.' and '0' mark an empty cell, anything after
+//   those 81 cells is kept as metadata
+pub struct PuzzleReader<R> {
+    reader: R,
+    line: String,
+    line_number: usize,
+    header_checked: bool,
+    count: Option<usize>,
+}
+
+impl<R: BufRead> PuzzleReader<R> {
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            line: String::new(),
+            line_number: 0,
+            header_checked: false,
+            count: None,
+        }
+    }
+
+    // The number of puzzles announced by the count header, only known after the first read
+    pub fn header_count(&self) -> Option<usize> {
+        self.count
+    }
+
+    pub fn line_number(&self) -> usize {
+        self.line_number
+    }
+
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+
+    fn parse_line(line: &str) -> Result<Puzzle, Error> {
+        let mut cells = [0; 81];
+        let mut chars = line.char_indices();
+        let mut rest = "";
+
+        for (i, cell) in cells.iter_mut().enumerate() {
+            match chars.next() {
+                Some((_, '.')) => *cell = 0,
+                Some((_, c)) => *cell = c.to_digit(10).ok_or(Error::InvalidCharacter(c))? as u8,
+                None => return Err(Error::InvalidLength(i)),
+            }
+        }
+        if let Some((idx, c)) = chars.next() {
+            if c == '.' || c.is_ascii_digit() {
+                let len = 81
+                    + line[idx..]
+                        .chars()
+                        .take_while(|&c| c == '.' || c.is_ascii_digit())
+                        .count();
+                return Err(Error::InvalidLength(len));
+            }
+            rest = &line[idx..];
+        }
+
+        Ok(Puzzle {
+            grid: Grid::from_cells(cells),
+            metadata: rest.trim().to_string(),
+        })
+    }
+}
+
+impl<R: BufRead> PuzzleReader<R> {
+    // Reads up to the next line that is neither empty nor a comment, leaving it in self.line
+    fn read_content_line(&mut self) -> Option<Result<(), Error>> {
+        loop {
+            self.line.clear();
+            match self.reader.read_line(&mut self.line) {
+                Ok(0) => return None,
+                Ok(_) => self.line_number += 1,
+                Err(err) => return Some(Err(Error::AtLine(self.line_number + 1, Box::new(err.into())))),
+            }
+            let line = self.line.trim();
+            if !line.is_empty() && !line.starts_with('#') {
+                return Some(Ok(()));
+            }
+        }
+    }
+
+    fn parse_current(&self) -> Result<Puzzle, Error> {
+        Self::parse_line(self.line.trim()).map_err(|err| Error::AtLine(self.line_number, Box::new(err)))
+    }
+}
+
+impl<R: BufRead> Iterator for PuzzleReader<R> {
+    type Item = Result<Puzzle, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Err(err) = self.read_content_line()? {
+            return Some(Err(err));
+        }
+        if !self.header_checked {
+            self.header_checked = true;
+            let line = self.line.trim();
+            if line.len() <= 10 && line.bytes().all(|b| b.is_ascii_digit()) {
+                if let Ok(count) = line.parse() {
+                    self.count = Some(count);
+                    return self.next();
+                }
+            }
+        }
+        Some(self.parse_current())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PUZZLE: &str = "530070000600195000098000060800060003400803001700020006060000280000419005000080079";
+
+    fn read(input: &str) -> (Vec<Result<Puzzle, Error>>, Option<usize>) {
+        let mut reader = PuzzleReader::new(input.as_bytes());
+        let puzzles = reader.by_ref().collect();
+        (puzzles, reader.header_count())
+    }
+
+    #[test]
+    fn header_only_file_is_empty() {
+        let (puzzles, count) = read("0\n");
+        assert!(puzzles.is_empty());
+        assert_eq!(count, Some(0));
+    }
+
+    #[test]
+    fn header_is_followed_by_puzzles_and_metadata() {
+        let (puzzles, count) = read(&format!(
+            "2\n# comment\n{} easy\n\n{}\n",
+            PUZZLE,
+            PUZZLE.replace('0', ".")
+        ));
+        assert_eq!(count, Some(2));
+        assert_eq!(puzzles.len(), 2);
+        let first = puzzles[0].as_ref().unwrap();
+        assert_eq!(first.grid.to_string_with('0'), PUZZLE);
+        assert_eq!(first.metadata, "easy");
+        assert_eq!(puzzles[1].as_ref().unwrap().grid, first.grid);
+    }
+
+    #[test]
+    fn broken_line_after_header_is_one_error() {
+        let (puzzles, count) = read(&format!("2\n{}\n{}\n", &PUZZLE[..80], PUZZLE));
+        assert_eq!(count, Some(2));
+        assert_eq!(puzzles.len(), 2);
+        assert!(matches!(&puzzles[0], Err(Error::AtLine(2, err)) if matches!(**err, Error::InvalidLength(80))));
+        assert!(puzzles[1].is_ok());
+    }
+
+    #[test]
+    fn only_the_first_line_can_be_a_header() {
+        let (puzzles, count) = read(&format!("{}\n5\n", PUZZLE));
+        assert_eq!(count, None);
+        assert!(puzzles[0].is_ok());
+        assert!(matches!(&puzzles[1], Err(Error::AtLine(2, err)) if matches!(**err, Error::InvalidLength(1))));
+    }
+}
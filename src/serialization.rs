@@ -0,0 +1,214 @@
+use crate::candidates::Candidates;
+use crate::grid::Grid;
+use crate::Sudoku;
+use serde::de::{self, Deserializer};
+use serde::ser::{SerializeSeq, Serializer};
+use serde::{Deserialize, Serialize};
+
+// Grids and sudokus serialize to their 81 character string by default, deserializing accepts
+// every notation understood by Grid::from_str. The string only holds the current cells, a sudoku
+// read back from it treats all of them as givens. Use the structured form to keep the givens.
+
+impl Serialize for Grid {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_string_with('0'))
+    }
+}
+
+impl<'de> Deserialize<'de> for Grid {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let input = <std::borrow::Cow<'de, str>>::deserialize(deserializer)?;
+        input.parse().map_err(de::Error::custom)
+    }
+}
+
+impl Serialize for Sudoku {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.to_grid().serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Sudoku {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Ok(Sudoku::from(Grid::deserialize(deserializer)?))
+    }
+}
+
+// Candidates serialize to a list of 81 candidate strings like "1479"
+impl Serialize for Candidates {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut seq = serializer.serialize_seq(Some(81))?;
+        for i in 0..81 {
+            seq.serialize_element(&self.cell_string(i))?;
+        }
+        seq.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for Candidates {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let cells = Vec::<String>::deserialize(deserializer)?;
+        cells.join(" ").parse().map_err(de::Error::custom)
+    }
+}
+
+// Structured alternative to the string form, use with #[serde(with = "suji::structured")].
+// A grid is written as {"rows": [[0, 0, 3, ...], ...]} with 0 for empty cells. A sudoku also keeps
+// its game state: "givens" when digits were placed besides them, "locked" and "history".
+pub mod structured {
+    use super::*;
+    use crate::history::History;
+
+    pub trait StructuredGrid: Sized {
+        fn to_structured(&self) -> Sudoku;
+        fn from_structured(sudoku: Sudoku) -> Self;
+    }
+
+    impl StructuredGrid for Grid {
+        fn to_structured(&self) -> Sudoku {
+            Sudoku::from(*self)
+        }
+
+        fn from_structured(sudoku: Sudoku) -> Self {
+            sudoku.to_grid()
+        }
+    }
+
+    impl StructuredGrid for Sudoku {
+        fn to_structured(&self) -> Sudoku {
+            self.clone()
+        }
+
+        fn from_structured(sudoku: Sudoku) -> Self {
+            sudoku
+        }
+    }
+
+    #[derive(Serialize, Deserialize)]
+    struct Rows {
+        rows: [[u8; 9]; 9],
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        givens: Option<[[u8; 9]; 9]>,
+        #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+        locked: bool,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        history: Option<History>,
+    }
+
+    fn to_rows(grid: &Grid) -> [[u8; 9]; 9] {
+        std::array::from_fn(|row| std::array::from_fn(|col| grid.get(row, col)))
+    }
+
+    fn from_rows<E: de::Error>(rows: &[[u8; 9]; 9]) -> Result<Grid, E> {
+        let mut grid = Grid::new();
+        for (i, row) in rows.iter().enumerate() {
+            for (j, &value) in row.iter().enumerate() {
+                if value > 9 {
+                    return Err(E::custom(format!("Invalid cell value {}", value)));
+                }
+                grid.set(i, j, value);
+            }
+        }
+        Ok(grid)
+    }
+
+    pub fn serialize<T: StructuredGrid, S: Serializer>(value: &T, serializer: S) -> Result<S::Ok, S::Error> {
+        let sudoku = value.to_structured();
+        let cells = sudoku.to_grid();
+        Rows {
+            rows: to_rows(&cells),
+            givens: (*sudoku.get_givens() != cells).then(|| to_rows(sudoku.get_givens())),
+            locked: sudoku.are_givens_locked(),
+            history: sudoku.history().cloned(),
+        }
+        .serialize(serializer)
+    }
+
+    pub fn deserialize<'de, T: StructuredGrid, D: Deserializer<'de>>(deserializer: D) -> Result<T, D::Error> {
+        let structured = Rows::deserialize(deserializer)?;
+        let cells = from_rows(&structured.rows)?;
+        let givens = match &structured.givens {
+            Some(givens) => from_rows(givens)?,
+            None => cells,
+        };
+
+        let mut sudoku = Sudoku::from(givens);
+        for (i, (&value, &given)) in cells.cells().iter().zip(givens.cells()).enumerate() {
+            if given != 0 && value != given {
+                return Err(de::Error::custom("cells do not match the givens"));
+            }
+            if value != given {
+                sudoku.set(i / 9, i % 9, value);
+            }
+        }
+        if let Some(history) = structured.history {
+            history.check().map_err(de::Error::custom)?;
+            sudoku.restore_history(history);
+        }
+        sudoku.lock_givens(structured.locked);
+        Ok(T::from_structured(sudoku))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PUZZLE: &str = "530070000600195000098000060800060003400803001700020006060000280000419005000080079";
+
+    #[derive(Serialize, Deserialize)]
+    struct Saved {
+        #[serde(with = "structured")]
+        sudoku: Sudoku,
+    }
+
+    #[test]
+    fn string_form_round_trips() {
+        let grid: Grid = PUZZLE.parse().unwrap();
+        let json = serde_json::to_string(&grid).unwrap();
+        assert_eq!(json, format!("\"{}\"", PUZZLE));
+        assert_eq!(serde_json::from_str::<Grid>(&json).unwrap(), grid);
+        assert!(serde_json::from_str::<Grid>("\"123\"").is_err());
+    }
+
+    #[test]
+    fn structured_form_keeps_givens_and_history() {
+        let mut sudoku = Sudoku::from(PUZZLE.parse::<Grid>().unwrap());
+        sudoku.lock_givens(true);
+        sudoku.enable_history();
+        sudoku.set(0, 2, 4);
+        sudoku.set(0, 3, 6);
+        sudoku.undo();
+
+        let json = serde_json::to_string(&Saved {
+            sudoku,
+        })
+        .unwrap();
+        let mut restored = serde_json::from_str::<Saved>(&json).unwrap().sudoku;
+        assert_eq!(restored.get_givens().to_string_with('0'), PUZZLE);
+        assert_eq!(restored.get(0, 2), 4);
+        assert!(restored.are_givens_locked());
+        assert!(restored.redo());
+        assert_eq!(restored.get(0, 3), 6);
+    }
+
+    #[test]
+    fn malformed_history_is_rejected() {
+        let rows = format!("{:?}", [[0u8; 9]; 9]);
+        let valid =
+            r#"{"moves": [{"row": 0, "col": 0, "old": 0, "new": 1}], "position": 0, "checkpoints": [["start", 1]]}"#;
+        let json = format!(r#"{{"sudoku": {{"rows": {}, "history": {}}}}}"#, rows, valid);
+        assert!(serde_json::from_str::<Saved>(&json).is_ok());
+
+        let documents = [
+            r#"{"moves": [], "position": 1, "checkpoints": []}"#,
+            r#"{"moves": [{"row": 9, "col": 0, "old": 0, "new": 1}], "position": 1, "checkpoints": []}"#,
+            r#"{"moves": [{"row": 0, "col": 0, "old": 0, "new": 10}], "position": 0, "checkpoints": []}"#,
+            r#"{"moves": [], "position": 0, "checkpoints": [["start", 2]]}"#,
+        ];
+        for history in documents {
+            let json = format!(r#"{{"sudoku": {{"rows": {}, "history": {}}}}}"#, rows, history);
+            assert!(serde_json::from_str::<Saved>(&json).is_err(), "{}", history);
+        }
+    }
+}
@@ -0,0 +1,182 @@
+use crate::error::Error;
+use crate::grid::Grid;
+
+// Cell indices of the rows, columns and boxes
+pub(crate) const UNITS: [[usize; 9]; 27] = units();
+
+const fn units() -> [[usize; 9]; 27] {
+    let mut units = [[0; 9]; 27];
+    let mut i = 0;
+    while i < 9 {
+        let mut j = 0;
+        while j < 9 {
+            units[i][j] = i * 9 + j;
+            units[9 + i][j] = j * 9 + i;
+            units[18 + i][j] = (i / 3 * 3 + j / 3) * 9 + i % 3 * 3 + j % 3;
+            j += 1;
+        }
+        i += 1;
+    }
+    units
+}
+
+// Exhaustive search used to count solutions, it always picks the empty cell with the fewest
+// possible values and stops as soon as `limit` solutions are found
+struct Counter {
+    cells: [u8; 81],
+    rows: [u16; 9],
+    cols: [u16; 9],
+    boxes: [u16; 9],
+    limit: usize,
+    count: usize,
+    first: Option<[u8; 81]>,
+}
+
+impl Counter {
+    // None if the clues already contain a duplicate or an invalid value
+    fn new(grid: &Grid, limit: usize) -> Option<Self> {
+        let mut counter = Self {
+            cells: *grid.cells(),
+            rows: [0; 9],
+            cols: [0; 9],
+            boxes: [0; 9],
+            limit,
+            count: 0,
+            first: None,
+        };
+        for i in 0..81 {
+            let value = counter.cells[i];
+            if value == 0 {
+                continue;
+            }
+            if value > 9 || counter.used(i) & 1 << (value - 1) != 0 {
+                return None;
+            }
+            let bit = 1 << (value - 1);
+            counter.toggle(i, bit);
+        }
+        Some(counter)
+    }
+
+    #[inline]
+    fn used(&self, cell: usize) -> u16 {
+        self.rows[cell / 9] | self.cols[cell % 9] | self.boxes[cell / 27 * 3 + cell % 9 / 3]
+    }
+
+    #[inline]
+    fn toggle(&mut self, cell: usize, bit: u16) {
+        self.rows[cell / 9] ^= bit;
+        self.cols[cell % 9] ^= bit;
+        self.boxes[cell / 27 * 3 + cell % 9 / 3] ^= bit;
+    }
+
+    fn search(&mut self) {
+        let mut possible = [0; 81];
+        let mut best = None;
+        let mut best_count = 10;
+        for (i, mask) in possible.iter_mut().enumerate() {
+            if self.cells[i] != 0 {
+                continue;
+            }
+            *mask = !self.used(i) & 0x1FF;
+            let count = mask.count_ones();
+            if count < best_count {
+                best = Some(i);
+                best_count = count;
+            }
+        }
+
+        let Some(mut cell) = best else {
+            self.count += 1;
+            if self.first.is_none() {
+                self.first = Some(self.cells);
+            }
+            return;
+        };
+        if best_count == 0 {
+            return;
+        }
+
+        let mut choices = possible[cell];
+        if best_count > 1 {
+            // a digit with a single place in a unit is forced, one without any place is a dead end
+            for unit in &UNITS {
+                let (mut once, mut twice, mut filled) = (0, 0, 0);
+                for &i in unit {
+                    if self.cells[i] != 0 {
+                        filled |= 1 << (self.cells[i] - 1);
+                        continue;
+                    }
+                    twice |= once & possible[i];
+                    once |= possible[i];
+                }
+                if once | filled != 0x1FF {
+                    return;
+                }
+                let singles = once & !twice;
+                if singles != 0 {
+                    choices = singles & singles.wrapping_neg();
+                    cell = unit
+                        .iter()
+                        .copied()
+                        .find(|&i| possible[i] & choices != 0)
+                        .unwrap_or(cell);
+                    break;
+                }
+            }
+        }
+
+        while choices != 0 && self.count < self.limit {
+            let bit = choices & choices.wrapping_neg();
+            choices ^= bit;
+            self.cells[cell] = bit.trailing_zeros() as u8 + 1;
+            self.toggle(cell, bit);
+            self.search();
+            self.toggle(cell, bit);
+        }
+        self.cells[cell] = 0;
+    }
+}
+
+fn run(grid: &Grid, limit: usize) -> Option<Counter> {
+    let mut counter = Counter::new(grid, limit)?;
+    if limit > 0 {
+        counter.search();
+    }
+    Some(counter)
+}
+
+impl Grid {
+    // Number of solutions, counting stops at limit
+    pub fn count_solutions(&self, limit: usize) -> usize {
+        run(self, limit).map_or(0, |counter| counter.count)
+    }
+
+    pub fn has_unique_solution(&self) -> bool {
+        self.count_solutions(2) == 1
+    }
+
+    pub fn is_solvable(&self) -> bool {
+        self.count_solutions(1) == 1
+    }
+
+    // Any solution of the grid, the same one every time
+    pub fn first_solution(&self) -> Option<Grid> {
+        run(self, 1)?.first.map(Grid::from_cells)
+    }
+
+    // The solution of the grid if there is exactly one
+    pub fn unique_solution(&self) -> Option<Grid> {
+        self.try_unique_solution().ok()
+    }
+
+    // Like unique_solution but tells apart puzzles without and with several solutions
+    pub fn try_unique_solution(&self) -> Result<Grid, Error> {
+        let counter = run(self, 2).ok_or(Error::NoSolution)?;
+        match (counter.count, counter.first) {
+            (1, Some(cells)) => Ok(Grid::from_cells(cells)),
+            (0, _) => Err(Error::NoSolution),
+            _ => Err(Error::MultipleSolutions),
+        }
+    }
+}
@@ -0,0 +1,225 @@
+use super::{Solver, SolverStats};
+use crate::rng::Rng;
+use crate::Sudoku;
+
+// Temperature schedule of the annealing solver
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AnnealingSchedule {
+    pub initial_temperature: f64,
+    // the temperature is multiplied by this after every level
+    pub cooling: f64,
+    pub steps_per_level: u64,
+    // levels without a new best cost before the boxes are shuffled again and the
+    // temperature starts over
+    pub reheat_after: u64,
+    // the solver gives up after this many proposed swaps
+    pub max_steps: u64,
+}
+
+impl Default for AnnealingSchedule {
+    fn default() -> Self {
+        Self {
+            initial_temperature: 0.5,
+            cooling: 0.995,
+            steps_per_level: 500,
+            reheat_after: 200,
+            max_steps: 20_000_000,
+        }
+    }
+}
+
+// Stochastic local search: every box is filled with a permutation of its missing digits, then
+// two free cells of a box are swapped at a time to minimize the number of digits missing from the
+// rows and columns. Worse swaps are accepted with probability exp(-delta / temperature). Unlike
+// the exact solvers it cannot prove that a puzzle has no solution, it gives up after max_steps.
+pub struct AnnealingSolver {
+    rng: Rng,
+    // mixed with the givens of every puzzle, so the run of a puzzle does not depend on which
+    // puzzles the solver saw before or on the thread it runs on
+    pub seed: u64,
+    pub schedule: AnnealingSchedule,
+    steps: u64,
+    restarts: u64,
+}
+
+// Cells of the puzzle together with how often every digit appears in every row and column
+struct State {
+    cells: [u8; 81],
+    rows: [[u8; 10]; 9],
+    cols: [[u8; 10]; 9],
+    cost: i64,
+}
+
+impl State {
+    fn new(cells: [u8; 81]) -> Self {
+        let mut state = Self {
+            cells,
+            rows: [[0; 10]; 9],
+            cols: [[0; 10]; 9],
+            cost: 0,
+        };
+        for (i, &value) in cells.iter().enumerate() {
+            state.rows[i / 9][value as usize] += 1;
+            state.cols[i % 9][value as usize] += 1;
+        }
+        for line in state.rows.iter().chain(&state.cols) {
+            state.cost += line[1..].iter().filter(|&&count| count == 0).count() as i64;
+        }
+        state
+    }
+
+    // Change in cost from replacing one occurrence of from with to in a line
+    fn replace(line: &mut [u8; 10], from: u8, to: u8) -> i64 {
+        let mut delta = 0;
+        line[from as usize] -= 1;
+        if line[from as usize] == 0 {
+            delta += 1;
+        }
+        if line[to as usize] == 0 {
+            delta -= 1;
+        }
+        line[to as usize] += 1;
+        delta
+    }
+
+    // Swaps the digits of two cells and returns the change in cost, swapping again undoes it
+    fn swap(&mut self, a: usize, b: usize) -> i64 {
+        let (first, second) = (self.cells[a], self.cells[b]);
+        let delta = Self::replace(&mut self.rows[a / 9], first, second)
+            + Self::replace(&mut self.cols[a % 9], first, second)
+            + Self::replace(&mut self.rows[b / 9], second, first)
+            + Self::replace(&mut self.cols[b % 9], second, first);
+        self.cells.swap(a, b);
+        self.cost += delta;
+        delta
+    }
+}
+
+impl AnnealingSolver {
+    // The same seed and schedule always give the same run
+    pub fn new(seed: u64) -> Self {
+        AnnealingSolver {
+            rng: Rng::new(seed),
+            seed,
+            schedule: AnnealingSchedule::default(),
+            steps: 0,
+            restarts: 0,
+        }
+    }
+
+    // Proposed swaps of the last call to solve
+    pub fn get_steps(&self) -> u64 {
+        self.steps
+    }
+
+    pub fn get_restarts(&self) -> u64 {
+        self.restarts
+    }
+
+    // FNV-1a of the givens
+    fn puzzle_seed(&self, givens: &[u8; 81]) -> u64 {
+        givens.iter().fold(self.seed ^ 0xCBF2_9CE4_8422_2325, |hash, &value| {
+            (hash ^ value as u64).wrapping_mul(0x0100_0000_01B3)
+        })
+    }
+
+    // Fills the free cells of every box with the digits missing from it in random order
+    fn fill(&mut self, givens: &[u8; 81], boxes: &[Vec<usize>]) -> State {
+        let mut cells = *givens;
+        for (index, free) in boxes.iter().enumerate() {
+            let mut used = [false; 10];
+            for i in 0..9 {
+                used[givens[(index / 3 * 3 + i / 3) * 9 + index % 3 * 3 + i % 3] as usize] = true;
+            }
+            let mut missing = (1..=9).filter(|&digit| !used[digit as usize]).collect::<Vec<u8>>();
+            self.rng.shuffle(&mut missing);
+            for (&cell, digit) in free.iter().zip(missing) {
+                cells[cell] = digit;
+            }
+        }
+        State::new(cells)
+    }
+
+    fn anneal(&mut self, givens: &[u8; 81]) -> Option<[u8; 81]> {
+        let schedule = self.schedule;
+        let boxes = (0..9)
+            .map(|index| {
+                (0..9)
+                    .map(|i| (index / 3 * 3 + i / 3) * 9 + index % 3 * 3 + i % 3)
+                    .filter(|&cell| givens[cell] == 0)
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        // only boxes with two free cells allow a swap
+        let movable = (0..9).filter(|&index| boxes[index].len() > 1).collect::<Vec<_>>();
+
+        let mut state = self.fill(givens, &boxes);
+        let mut temperature = schedule.initial_temperature;
+        let mut best = state.cost;
+        let mut stale = 0;
+        let mut level_steps = 0;
+        while state.cost > 0 {
+            if movable.is_empty() || self.steps >= schedule.max_steps {
+                return None;
+            }
+            self.steps += 1;
+
+            let free = &boxes[movable[self.rng.below(movable.len())]];
+            let a = self.rng.below(free.len());
+            let b = (a + 1 + self.rng.below(free.len() - 1)) % free.len();
+            let delta = state.swap(free[a], free[b]);
+            if delta > 0 && self.rng.next_f64() >= (-delta as f64 / temperature).exp() {
+                state.swap(free[a], free[b]);
+            }
+
+            level_steps += 1;
+            if level_steps >= schedule.steps_per_level {
+                level_steps = 0;
+                temperature *= schedule.cooling;
+                if state.cost < best {
+                    best = state.cost;
+                    stale = 0;
+                } else {
+                    stale += 1;
+                }
+                if stale >= schedule.reheat_after {
+                    self.restarts += 1;
+                    state = self.fill(givens, &boxes);
+                    temperature = schedule.initial_temperature;
+                    best = state.cost;
+                    stale = 0;
+                }
+            }
+        }
+        Some(state.cells)
+    }
+}
+
+impl Default for AnnealingSolver {
+    fn default() -> Self {
+        Self::new(0)
+    }
+}
+
+impl Solver for AnnealingSolver {
+    fn solve(&mut self, input: &Sudoku) -> Option<String> {
+        self.steps = 0;
+        self.restarts = 0;
+        // conflicting givens would make the search run until max_steps
+        if !input.is_valid() {
+            return None;
+        }
+        let grid = input.to_grid();
+        self.rng = Rng::new(self.puzzle_seed(grid.cells()));
+        let solution = self.anneal(grid.cells())?;
+        Some(solution.iter().map(|&value| (b'0' + value) as char).collect())
+    }
+
+    // Every restart throws away the whole fill and guesses a new one
+    fn stats(&self) -> SolverStats {
+        SolverStats {
+            guesses: self.restarts,
+        }
+    }
+}
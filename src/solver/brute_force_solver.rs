@@ -9,9 +9,15 @@ impl BruteForceSolver {
     }
 }
 
+impl Default for BruteForceSolver {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Solver for BruteForceSolver {
     fn solve(&mut self, input: &Sudoku) -> Option<String> {
-        let mut sudoku = input.clone();
+        let mut sudoku = input.clone_without_history();
         let empty_cells = input.get_empty_cells();
 
         let mut i = 0;
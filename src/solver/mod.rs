@@ -1,11 +1,38 @@
+use crate::candidates::Candidates;
 use crate::Sudoku;
 
+mod annealing_solver;
 mod brute_force_solver;
+mod sat_solver;
 mod wave_function_collapse_solver;
 
+pub use annealing_solver::{AnnealingSchedule, AnnealingSolver};
 pub use brute_force_solver::BruteForceSolver;
-pub use wave_function_collapse_solver::WaveFunctionCollapseSolver;
+pub use sat_solver::SatSolver;
+pub use wave_function_collapse_solver::{CellSelection, Heuristics, ValueOrder, WaveFunctionCollapseSolver};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SolverStats {
+    pub guesses: u64,
+}
 
 pub trait Solver {
     fn solve(&mut self, input: &Sudoku) -> Option<String>;
+
+    // Statistics of the last call to solve
+    fn stats(&self) -> SolverStats {
+        SolverStats::default()
+    }
+
+    // Solves the sudoku while only allowing the given candidates in its empty cells.
+    // Solvers without a notion of candidates fall back to checking their solution against them.
+    fn solve_with_candidates(&mut self, input: &Sudoku, candidates: &Candidates) -> Option<String> {
+        let solution = self.solve(input)?;
+        let consistent = solution
+            .bytes()
+            .enumerate()
+            .all(|(i, chr)| input.get(i / 9, i % 9) != 0 || candidates.contains(i / 9, i % 9, chr - b'0'));
+        consistent.then_some(solution)
+    }
 }
@@ -0,0 +1,453 @@
+use super::{Solver, SolverStats};
+use crate::candidates::Candidates;
+use crate::dimacs::Cnf;
+use crate::error::Error;
+use crate::variants::Variants;
+use crate::Sudoku;
+
+// Conflict driven clause learning over the CNF encoding of the sudoku. Knows nothing about
+// sudoku itself, any extra constraint expressed as clauses is handled by the same engine.
+//
+// Literals are stored as 2 * variable + (1 if negated), variables start at 0.
+pub struct SatSolver {
+    // the literals of all clauses back to back, clauses are (start, length) ranges into it
+    literals: Vec<usize>,
+    clauses: Vec<(usize, usize)>,
+    // clauses watching a literal, visited when that literal becomes false
+    watches: Vec<Vec<usize>>,
+    // 1 true, -1 false, 0 unassigned
+    values: Vec<i8>,
+    levels: Vec<usize>,
+    reasons: Vec<Option<usize>>,
+    // last value of every variable, reused when it is picked again
+    phases: Vec<bool>,
+    activity: Vec<f64>,
+    increment: f64,
+    trail: Vec<usize>,
+    // trail length at the start of every decision level
+    trail_limits: Vec<usize>,
+    queue_head: usize,
+    seen: Vec<bool>,
+    decisions: u64,
+    conflicts: u64,
+}
+
+impl SatSolver {
+    pub fn new() -> Self {
+        SatSolver {
+            literals: Vec::new(),
+            clauses: Vec::new(),
+            watches: Vec::new(),
+            values: Vec::new(),
+            levels: Vec::new(),
+            reasons: Vec::new(),
+            phases: Vec::new(),
+            activity: Vec::new(),
+            increment: 1.0,
+            trail: Vec::new(),
+            trail_limits: Vec::new(),
+            queue_head: 0,
+            seen: Vec::new(),
+            decisions: 0,
+            conflicts: 0,
+        }
+    }
+
+    pub fn get_decisions(&self) -> u64 {
+        self.decisions
+    }
+
+    pub fn get_conflicts(&self) -> u64 {
+        self.conflicts
+    }
+
+    // A satisfying assignment (index 0 is variable 1) or None if the formula is unsatisfiable
+    pub fn solve_cnf(&mut self, cnf: &Cnf) -> Option<Vec<bool>> {
+        if !self.load(cnf) {
+            return None;
+        }
+        loop {
+            if let Some(conflict) = self.propagate() {
+                self.conflicts += 1;
+                if self.trail_limits.is_empty() {
+                    return None;
+                }
+                let (learnt, level) = self.analyze(conflict);
+                self.backtrack(level);
+                let reason = (learnt.len() > 1).then(|| self.add_clause(&learnt));
+                self.assign(learnt[0], reason);
+                self.increment /= 0.95;
+                continue;
+            }
+
+            let Some(variable) = self.pick_variable() else {
+                return Some(self.values.iter().map(|&value| value == 1).collect());
+            };
+            self.decisions += 1;
+            self.trail_limits.push(self.trail.len());
+            self.assign(variable * 2 + !self.phases[variable] as usize, None);
+        }
+    }
+
+    // Resets the state to the given formula, false if it is trivially unsatisfiable
+    fn load(&mut self, cnf: &Cnf) -> bool {
+        let variables = cnf.variables;
+        self.literals.clear();
+        self.clauses.clear();
+        self.watches = vec![Vec::new(); variables * 2];
+        self.values = vec![0; variables];
+        self.levels = vec![0; variables];
+        self.reasons = vec![None; variables];
+        self.phases = vec![false; variables];
+        self.activity = vec![0.0; variables];
+        self.increment = 1.0;
+        self.trail.clear();
+        self.trail_limits.clear();
+        self.queue_head = 0;
+        self.seen = vec![false; variables];
+        self.decisions = 0;
+        self.conflicts = 0;
+
+        let mut literals = Vec::new();
+        for clause in &cnf.clauses {
+            literals.clear();
+            for &literal in clause {
+                let variable = literal.unsigned_abs() as usize;
+                if variable == 0 || variable > variables {
+                    return false;
+                }
+                literals.push((variable - 1) * 2 + (literal < 0) as usize);
+            }
+            literals.sort_unstable();
+            literals.dedup();
+            if literals.windows(2).any(|pair| pair[0] ^ 1 == pair[1]) {
+                continue; // always true
+            }
+            match literals.len() {
+                0 => return false,
+                1 => match self.value(literals[0]) {
+                    0 => self.assign(literals[0], None),
+                    -1 => return false,
+                    _ => {}
+                },
+                _ => {
+                    self.add_clause(&literals);
+                }
+            }
+        }
+        true
+    }
+
+    fn value(&self, literal: usize) -> i8 {
+        let value = self.values[literal / 2];
+        if literal & 1 == 1 {
+            -value
+        } else {
+            value
+        }
+    }
+
+    fn assign(&mut self, literal: usize, reason: Option<usize>) {
+        let variable = literal / 2;
+        self.values[variable] = if literal & 1 == 1 { -1 } else { 1 };
+        self.levels[variable] = self.trail_limits.len();
+        self.reasons[variable] = reason;
+        self.trail.push(literal);
+    }
+
+    // The first two literals are watched
+    fn add_clause(&mut self, literals: &[usize]) -> usize {
+        let index = self.clauses.len();
+        self.watches[literals[0]].push(index);
+        self.watches[literals[1]].push(index);
+        self.clauses.push((self.literals.len(), literals.len()));
+        self.literals.extend_from_slice(literals);
+        index
+    }
+
+    fn clause(&self, index: usize) -> &[usize] {
+        let (start, length) = self.clauses[index];
+        &self.literals[start..start + length]
+    }
+
+    fn clause_mut(&mut self, index: usize) -> &mut [usize] {
+        let (start, length) = self.clauses[index];
+        &mut self.literals[start..start + length]
+    }
+
+    // Unit propagation with two watched literals, returns a clause that became false
+    fn propagate(&mut self) -> Option<usize> {
+        while self.queue_head < self.trail.len() {
+            let falsified = self.trail[self.queue_head] ^ 1;
+            self.queue_head += 1;
+
+            let watching = std::mem::take(&mut self.watches[falsified]);
+            let mut kept = Vec::with_capacity(watching.len());
+            let mut conflict = None;
+            for (i, &index) in watching.iter().enumerate() {
+                if conflict.is_some() {
+                    kept.extend_from_slice(&watching[i..]);
+                    break;
+                }
+                let clause = self.clause_mut(index);
+                if clause[0] == falsified {
+                    clause.swap(0, 1);
+                }
+                let first = clause[0];
+                if self.value(first) == 1 {
+                    kept.push(index);
+                    continue;
+                }
+
+                let clause = self.clause(index);
+                if let Some(k) = (2..clause.len()).find(|&k| self.value(clause[k]) != -1) {
+                    let clause = self.clause_mut(index);
+                    clause.swap(1, k);
+                    let watched = clause[1];
+                    self.watches[watched].push(index);
+                    continue;
+                }
+
+                kept.push(index);
+                if self.value(first) == -1 {
+                    conflict = Some(index);
+                } else {
+                    self.assign(first, Some(index));
+                }
+            }
+            self.watches[falsified] = kept;
+            if conflict.is_some() {
+                return conflict;
+            }
+        }
+        None
+    }
+
+    // First unique implication point learning: returns the learnt clause with the asserting
+    // literal first and the level to jump back to
+    fn analyze(&mut self, conflict: usize) -> (Vec<usize>, usize) {
+        let level = self.trail_limits.len();
+        let mut learnt = vec![0];
+        let mut pending = 0;
+        let mut clause = conflict;
+        let mut index = self.trail.len();
+        let mut implied = None;
+        loop {
+            // reason clauses hold the literal they implied at position 0
+            let skip = implied.is_some() as usize;
+            for k in skip..self.clauses[clause].1 {
+                let literal = self.clause(clause)[k];
+                let variable = literal / 2;
+                if self.seen[variable] || self.levels[variable] == 0 {
+                    continue;
+                }
+                self.seen[variable] = true;
+                self.bump(variable);
+                if self.levels[variable] == level {
+                    pending += 1;
+                } else {
+                    learnt.push(literal);
+                }
+            }
+
+            loop {
+                index -= 1;
+                if self.seen[self.trail[index] / 2] {
+                    break;
+                }
+            }
+            let literal = self.trail[index];
+            self.seen[literal / 2] = false;
+            pending -= 1;
+            if pending == 0 {
+                learnt[0] = literal ^ 1;
+                break;
+            }
+            implied = Some(literal);
+            clause = self.reasons[literal / 2].expect("only decisions have no reason");
+        }
+
+        for &literal in &learnt[1..] {
+            self.seen[literal / 2] = false;
+        }
+        // the second watch has to be the literal that gets unassigned last
+        let mut back_level = 0;
+        for k in 1..learnt.len() {
+            let level = self.levels[learnt[k] / 2];
+            if level > back_level {
+                back_level = level;
+                learnt.swap(1, k);
+            }
+        }
+        (learnt, back_level)
+    }
+
+    fn backtrack(&mut self, level: usize) {
+        if self.trail_limits.len() <= level {
+            return;
+        }
+        let start = self.trail_limits[level];
+        for &literal in &self.trail[start..] {
+            let variable = literal / 2;
+            self.phases[variable] = literal & 1 == 0;
+            self.values[variable] = 0;
+            self.reasons[variable] = None;
+        }
+        self.trail.truncate(start);
+        self.trail_limits.truncate(level);
+        self.queue_head = start;
+    }
+
+    fn bump(&mut self, variable: usize) {
+        self.activity[variable] += self.increment;
+        if self.activity[variable] > 1e100 {
+            for activity in &mut self.activity {
+                *activity *= 1e-100;
+            }
+            self.increment *= 1e-100;
+        }
+    }
+
+    // The unassigned variable that took part in the most recent conflicts
+    fn pick_variable(&self) -> Option<usize> {
+        let mut best = None;
+        let mut best_activity = -1.0;
+        for (variable, &activity) in self.activity.iter().enumerate() {
+            if self.values[variable] == 0 && activity > best_activity {
+                best = Some(variable);
+                best_activity = activity;
+            }
+        }
+        best
+    }
+
+    // Solves the sudoku under the variant rules, jigsaw regions take the place of the boxes
+    pub fn solve_variants(&mut self, input: &Sudoku, variants: &Variants) -> Result<Option<String>, Error> {
+        Ok(self.solve_encoded(&input.to_cnf_with(variants)?))
+    }
+
+    fn solve_encoded(&mut self, cnf: &Cnf) -> Option<String> {
+        let model = self.solve_cnf(cnf)?;
+        let mut solution = vec![b'0'; 81];
+        for (variable, _) in model.iter().enumerate().filter(|(_, &value)| value) {
+            // auxiliary variables of variant rules come after the cells
+            let Some((row, col, digit)) = Cnf::cell_of(variable as i32 + 1) else {
+                break;
+            };
+            solution[row * 9 + col] = b'0' + digit;
+        }
+        String::from_utf8(solution).ok()
+    }
+}
+
+impl Default for SatSolver {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Solver for SatSolver {
+    fn solve(&mut self, input: &Sudoku) -> Option<String> {
+        self.solve_encoded(&input.to_cnf())
+    }
+
+    fn solve_with_candidates(&mut self, input: &Sudoku, candidates: &Candidates) -> Option<String> {
+        let mut cnf = input.to_cnf();
+        for cell in 0..81 {
+            let (row, col) = (cell / 9, cell % 9);
+            if input.get(row, col) != 0 {
+                continue;
+            }
+            for digit in 1..=9 {
+                if !candidates.contains(row, col, digit) {
+                    cnf.clauses.push(vec![-Cnf::variable(row, col, digit)]);
+                }
+            }
+        }
+        self.solve_encoded(&cnf)
+    }
+
+    // Decisions are reported as guesses
+    fn stats(&self) -> SolverStats {
+        SolverStats {
+            guesses: self.decisions,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grid::Grid;
+    use crate::variants::Cage;
+
+    const PUZZLE: &str = "000000907000420180000705026100904000050000040000507009920108000034059000507000000";
+
+    #[test]
+    fn solves_like_the_exact_solver() {
+        let grid = PUZZLE.parse::<Grid>().unwrap();
+        let solution = SatSolver::new().solve(&PUZZLE.parse().unwrap()).unwrap();
+        assert_eq!(solution, grid.unique_solution().unwrap().to_string());
+    }
+
+    #[test]
+    fn contradictions_have_no_solution() {
+        let mut solver = SatSolver::new();
+        // two 9s in the first row
+        let puzzle = PUZZLE.replacen('0', "9", 1);
+        assert_eq!(solver.solve(&puzzle.parse().unwrap()), None);
+
+        let cnf = Cnf {
+            variables: 2,
+            clauses: vec![vec![1, 2], vec![-1, 2], vec![1, -2], vec![-1, -2]],
+        };
+        assert_eq!(solver.solve_cnf(&cnf), None);
+        let cnf = Cnf {
+            variables: 2,
+            clauses: vec![vec![1, 2], vec![-1]],
+        };
+        assert_eq!(solver.solve_cnf(&cnf), Some(vec![false, true]));
+    }
+
+    #[test]
+    fn variant_rules_are_respected() {
+        let empty = Sudoku::from(Grid::new());
+        let mut solver = SatSolver::new();
+
+        let variants = Variants {
+            cages: vec![Cage {
+                cells: vec![(4, 4), (4, 5)],
+                sum: Some(17),
+            }],
+            diagonals: true,
+            regions: None,
+        };
+        let solution = solver.solve_variants(&empty, &variants).unwrap().unwrap();
+        let grid = solution.parse::<Grid>().unwrap();
+        let cells = grid.cells();
+        for unit in variants.units().unwrap() {
+            let mask = unit.iter().fold(0u16, |mask, &cell| mask | 1 << cells[cell]);
+            assert_eq!(mask, 0x3FE, "{}", solution);
+        }
+        assert_eq!(cells[40] + cells[41], 17);
+
+        // a cage of two cells can never add up to 18
+        let impossible = Variants {
+            cages: vec![Cage {
+                cells: vec![(0, 0), (0, 1)],
+                sum: Some(18),
+            }],
+            ..Variants::default()
+        };
+        assert_eq!(solver.solve_variants(&empty, &impossible).unwrap(), None);
+
+        let invalid = Variants {
+            regions: Some([[9; 9]; 9]),
+            ..Variants::default()
+        };
+        assert!(matches!(
+            solver.solve_variants(&empty, &invalid),
+            Err(Error::InvalidVariant(_))
+        ));
+    }
+}
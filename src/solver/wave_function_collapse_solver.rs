@@ -1,4 +1,7 @@
-use super::Solver;
+use super::{Solver, SolverStats};
+use crate::candidates::Candidates;
+use crate::logic::{LogicState, Technique};
+use crate::rng::Rng;
 use crate::Sudoku;
 
 const BOXES: [[usize; 9]; 9] = [
@@ -25,16 +28,102 @@ const BOX_TO_CELLS: [[usize; 9]; 9] = [
     [60, 61, 62, 69, 70, 71, 78, 79, 80],
 ];
 
+// The 20 cells sharing a row, column or box with every cell
+const PEERS: [[usize; 20]; 81] = peers();
+
+const fn peers() -> [[usize; 20]; 81] {
+    let mut peers = [[0; 20]; 81];
+    let mut cell = 0;
+    while cell < 81 {
+        let (row, col) = (cell / 9, cell % 9);
+        let mut count = 0;
+        let mut other = 0;
+        while other < 81 {
+            let (r, c) = (other / 9, other % 9);
+            let shared = r == row || c == col || (r / 3 == row / 3 && c / 3 == col / 3);
+            if other != cell && shared {
+                peers[cell][count] = other;
+                count += 1;
+            }
+            other += 1;
+        }
+        cell += 1;
+    }
+    peers
+}
+
+// How the next cell to branch on is picked. Cells with a single remaining value are always
+// taken first, as they need no guess.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum CellSelection {
+    // The first cell with the fewest remaining values
+    #[default]
+    MinimumRemainingValues,
+    // The cell with the fewest remaining values, ties broken by the most empty peers
+    MostConstrainedPeers,
+    // A random cell among those with the fewest remaining values
+    RandomTieBreak,
+}
+
+// The order in which the values of the branching cell are tried
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ValueOrder {
+    #[default]
+    Ascending,
+    // Values that remove the fewest options from the empty peers first
+    LeastConstraining,
+    Random,
+}
+
+// Branching and propagation choices of the solver, the default is the original solver
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Heuristics {
+    pub cell_selection: CellSelection,
+    pub value_order: ValueOrder,
+    // Fill cells with a single remaining value during propagation instead of branching on them
+    pub singles: bool,
+    // Fill the only cell of a row, column or box that can hold a value (get_uniques)
+    pub hidden_singles: bool,
+    // Logical techniques applied once the enabled singles stages are stuck
+    pub techniques: Vec<Technique>,
+    // Seed of the random choices, reset at the start of every solve
+    pub seed: u64,
+}
+
+impl Default for Heuristics {
+    fn default() -> Self {
+        Self {
+            cell_selection: CellSelection::default(),
+            value_order: ValueOrder::default(),
+            singles: false,
+            hidden_singles: true,
+            techniques: Vec::new(),
+            seed: 0,
+        }
+    }
+}
+
 pub struct WaveFunctionCollapseSolver {
     permutations: [u16; 81],
     guesses: u64,
+    pub heuristics: Heuristics,
+    rng: Rng,
 }
 
 impl WaveFunctionCollapseSolver {
     pub fn new() -> Self {
+        Self::with_heuristics(Heuristics::default())
+    }
+
+    pub fn with_heuristics(heuristics: Heuristics) -> Self {
         WaveFunctionCollapseSolver {
             permutations: [0; 81],
             guesses: 0,
+            rng: Rng::new(heuristics.seed),
+            heuristics,
         }
     }
 
@@ -44,7 +133,7 @@ impl WaveFunctionCollapseSolver {
 
     fn generate_permutations(&mut self, sudoku: &Sudoku) {
         for i in 0..81 {
-            self.permutations[i] = sudoku.get_possible_values(i / 9, i % 9);
+            self.permutations[i] = sudoku.get_possible_values(i / 9, i % 9) & sudoku.get_candidates(i / 9, i % 9);
         }
     }
 
@@ -58,22 +147,62 @@ impl WaveFunctionCollapseSolver {
     //       the number of needed calls to solve_recursive() will increase exponentially.
     //       In this case logic_process() will reduce the needed recursive calls enough to make
     //       up for its own cost.
+    //
+    // 2. Cells with a single value left and the enabled logical techniques, see Heuristics.
     fn logic_process(&mut self, sudoku: &mut Sudoku) {
-        let mut uniques = self.get_uniques();
-
-        while !uniques.is_empty() {
-            for (cell, value) in uniques {
-                if sudoku.get(cell / 9, cell % 9) != 0 {
-                    continue;
+        loop {
+            let mut progress = false;
+            if self.heuristics.singles {
+                for cell in 0..81 {
+                    let permutation = self.permutations[cell];
+                    if permutation.count_ones() == 1 && sudoku.get(cell / 9, cell % 9) == 0 {
+                        let value = permutation.trailing_zeros() as u8 + 1;
+                        sudoku.set_not_zero(cell / 9, cell % 9, value);
+                        self.collapse(cell, value);
+                        progress = true;
+                    }
                 }
-                sudoku.set_not_zero(cell / 9, cell % 9, value);
-                self.collapse(cell, value);
             }
-            uniques = self.get_uniques();
+            if self.heuristics.hidden_singles {
+                for (cell, value) in self.get_uniques() {
+                    if sudoku.get(cell / 9, cell % 9) != 0 {
+                        continue;
+                    }
+                    sudoku.set_not_zero(cell / 9, cell % 9, value);
+                    self.collapse(cell, value);
+                    progress = true;
+                }
+            }
+            if !progress && !self.heuristics.techniques.is_empty() {
+                progress = self.apply_technique(sudoku);
+            }
+            if !progress {
+                return;
+            }
         }
     }
 
+    // Applies the first step the enabled techniques find, returns whether there was one
+    fn apply_technique(&mut self, sudoku: &mut Sudoku) -> bool {
+        let state = LogicState {
+            values: std::array::from_fn(|cell| sudoku.get(cell / 9, cell % 9)),
+            candidates: self.permutations,
+        };
+        let Some(step) = state.find_step(&self.heuristics.techniques) else {
+            return false;
+        };
+        for &(row, col, value) in &step.eliminations {
+            self.permutations[row * 9 + col] &= !(1 << (value - 1));
+        }
+        if let Some((row, col, value)) = step.placement {
+            sudoku.set_not_zero(row, col, value);
+            self.collapse(row * 9 + col, value);
+        }
+        true
+    }
+
     // return the position of all permutations that are unique to a cell in a row, column or box
+    #[allow(clippy::needless_range_loop)]
     fn get_uniques(&mut self) -> Vec<(usize, u8)> {
         let mut uniques = Vec::new();
         for i in 0..9 {
@@ -111,12 +240,13 @@ impl WaveFunctionCollapseSolver {
         uniques
     }
 
-    fn next_best_cell(&self, sudoku: &Sudoku) -> Option<(usize, Vec<u8>)> {
+    fn next_best_cell(&mut self, sudoku: &Sudoku) -> Option<(usize, Vec<u8>)> {
         let mut best_cell = None;
         let mut best_count = 10u8;
-        let mut best_values = Vec::new();
-        for i in 0..81 {
-            let count = self.permutations[i].count_ones() as u8;
+        let mut best_peers = 0;
+        let mut ties = Vec::new();
+        for (i, &permutation) in self.permutations.iter().enumerate() {
+            let count = permutation.count_ones() as u8;
             if count == 0 {
                 if sudoku.get(i / 9, i % 9) != 0 {
                     continue;
@@ -124,18 +254,61 @@ impl WaveFunctionCollapseSolver {
                 return None;
             }
             if count == 1 {
-                return Some((i, vec![self.permutations[i].trailing_zeros() as u8 + 1]));
+                return Some((i, vec![permutation.trailing_zeros() as u8 + 1]));
             }
-            if count < best_count {
-                best_count = count;
-                best_cell = Some(i);
-                best_values = (1..=9).filter(|&v| self.permutations[i] & 1 << (v - 1) != 0).collect();
+            match self.heuristics.cell_selection {
+                CellSelection::MinimumRemainingValues => {
+                    if count < best_count {
+                        best_count = count;
+                        best_cell = Some(i);
+                    }
+                }
+                // the peers are only counted for cells that can still win on the count
+                CellSelection::MostConstrainedPeers if count <= best_count => {
+                    let peers = PEERS[i].iter().filter(|&&peer| self.permutations[peer] != 0).count();
+                    if count < best_count || peers > best_peers {
+                        best_peers = peers;
+                        best_count = count;
+                        best_cell = Some(i);
+                    }
+                }
+                CellSelection::MostConstrainedPeers => {}
+                CellSelection::RandomTieBreak => {
+                    if count < best_count {
+                        best_count = count;
+                        ties.clear();
+                    }
+                    if count == best_count {
+                        ties.push(i);
+                    }
+                }
             }
         }
+        if !ties.is_empty() {
+            best_cell = Some(ties[self.rng.below(ties.len())]);
+        }
 
-        best_cell.map(|cell| (cell, best_values))
+        best_cell.map(|cell| (cell, self.order_values(cell)))
     }
 
+    fn order_values(&mut self, cell: usize) -> Vec<u8> {
+        let mut values = (1..=9)
+            .filter(|&v| self.permutations[cell] & 1 << (v - 1) != 0)
+            .collect::<Vec<u8>>();
+        match self.heuristics.value_order {
+            ValueOrder::Ascending => {}
+            ValueOrder::LeastConstraining => values.sort_by_key(|&value| {
+                PEERS[cell]
+                    .iter()
+                    .filter(|&&peer| self.permutations[peer] & 1 << (value - 1) != 0)
+                    .count()
+            }),
+            ValueOrder::Random => self.rng.shuffle(&mut values),
+        }
+        values
+    }
+
+    #[allow(clippy::needless_range_loop)]
     fn collapse(&mut self, cell: usize, value: u8) {
         self.permutations[cell] = 0;
         let mask = !(1 << (value - 1));
@@ -162,8 +335,8 @@ impl WaveFunctionCollapseSolver {
 
         let (row, col) = (cell / 9, cell % 9);
         for value in values {
-            let mut new_sudoku = sudoku.clone();
-            let perm_state = self.permutations.clone();
+            let mut new_sudoku = sudoku.clone_without_history();
+            let perm_state = self.permutations;
             new_sudoku.set_not_zero(row, col, value);
             self.collapse(cell, value);
             self.guesses += 1;
@@ -181,12 +354,37 @@ impl WaveFunctionCollapseSolver {
     }
 }
 
+impl Default for WaveFunctionCollapseSolver {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Solver for WaveFunctionCollapseSolver {
     fn solve(&mut self, input: &Sudoku) -> Option<String> {
-        let mut new_sudoku = input.clone();
+        let mut new_sudoku = input.clone_without_history();
+        self.guesses = 0;
+        self.rng = Rng::new(self.heuristics.seed);
+        self.generate_permutations(&new_sudoku);
+        self.logic_process(&mut new_sudoku);
+        self.solve_recursive(&new_sudoku)
+    }
+
+    fn solve_with_candidates(&mut self, input: &Sudoku, candidates: &Candidates) -> Option<String> {
+        let mut new_sudoku = input.clone_without_history();
         self.guesses = 0;
+        self.rng = Rng::new(self.heuristics.seed);
         self.generate_permutations(&new_sudoku);
+        for (permutation, mask) in self.permutations.iter_mut().zip(candidates.masks()) {
+            *permutation &= mask;
+        }
         self.logic_process(&mut new_sudoku);
         self.solve_recursive(&new_sudoku)
     }
+
+    fn stats(&self) -> SolverStats {
+        SolverStats {
+            guesses: self.guesses,
+        }
+    }
 }
@@ -1,30 +1,78 @@
 use crate::backend::{BackendType, CloneableBackend};
+use crate::candidates::Candidates;
+use crate::error::Error;
+use crate::grid::Grid;
+use crate::history::{History, Move};
+use crate::logic::{self, Hint, HintLevel, Rating, Step};
 use crate::solver::Solver;
+use crate::svg::SvgRenderer;
+use crate::unit::Conflict;
+use std::str::FromStr;
 
 #[derive(Debug)]
 pub struct Sudoku {
     backend: Box<dyn CloneableBackend>,
+    // The clues of the loaded puzzle
+    givens: Grid,
+    givens_locked: bool,
+    history: Option<History>,
 }
 
 impl Sudoku {
     pub fn new(backend: BackendType) -> Self {
         match backend {
-            BackendType::BitfieldGrid => Self {
-                backend: Box::new(crate::backend::BitfieldGrid::new()),
-            },
+            BackendType::BitfieldGrid => Self::new_custom(Box::new(crate::backend::BitfieldGrid::new())),
         }
     }
 
     pub fn new_custom(backend: Box<dyn CloneableBackend>) -> Self {
         Self {
             backend,
+            givens: Grid::new(),
+            givens_locked: false,
+            history: None,
         }
     }
 
     pub fn load_from_str(&mut self, input: &str, null_chr: char) {
         self.backend.load_from_str(input, null_chr);
+        self.givens = self.backend.to_grid();
+        self.clear_history();
     }
 
+    pub fn try_load_from_str(&mut self, input: &str, null_chr: char) -> Result<(), Error> {
+        self.backend.try_load_from_str(input, null_chr)?;
+        self.givens = self.backend.to_grid();
+        self.clear_history();
+        Ok(())
+    }
+
+    // Accepts every notation Grid::from_str understands, e.g. multi-line or boxed grids
+    pub fn load_from_text(&mut self, input: &str) -> Result<(), Error> {
+        self.load_grid(&input.parse()?);
+        Ok(())
+    }
+
+    pub fn load_grid(&mut self, grid: &Grid) {
+        self.backend.load_grid(grid);
+        self.givens = *grid;
+        self.clear_history();
+    }
+
+    pub fn to_grid(&self) -> Grid {
+        self.backend.to_grid()
+    }
+
+    // Compact binary form of the current cells, see the binary module for the layout
+    pub fn encode(&self) -> Vec<u8> {
+        self.to_grid().encode_puzzle()
+    }
+
+    pub fn decode(bytes: &[u8]) -> Result<Self, Error> {
+        Ok(Self::from(Grid::decode_puzzle(bytes)?))
+    }
+
+    #[allow(clippy::inherent_to_string_shadow_display)]
     pub fn to_string(&self) -> String {
         self.backend.to_string('0')
     }
@@ -33,23 +81,207 @@ impl Sudoku {
         self.backend.get(row, col)
     }
 
+    // Setting or unsetting a locked given is ignored, use try_set / try_unset to detect it
     pub fn set(&mut self, row: usize, col: usize, value: u8) {
+        if self.is_locked(row, col) {
+            return;
+        }
+        self.record(row, col, value);
         self.backend.set(row, col, value)
     }
 
     pub fn unset(&mut self, row: usize, col: usize) {
+        if self.is_locked(row, col) {
+            return;
+        }
+        self.record(row, col, 0);
         self.backend.unset(row, col)
     }
 
+    pub fn try_set(&mut self, row: usize, col: usize, value: u8) -> Result<(), Error> {
+        if self.is_locked(row, col) {
+            return Err(Error::LockedCell(row, col));
+        }
+        self.record(row, col, value);
+        self.backend.set(row, col, value);
+        Ok(())
+    }
+
+    pub fn try_unset(&mut self, row: usize, col: usize) -> Result<(), Error> {
+        if self.is_locked(row, col) {
+            return Err(Error::LockedCell(row, col));
+        }
+        self.record(row, col, 0);
+        self.backend.unset(row, col);
+        Ok(())
+    }
+
+    pub fn is_given(&self, row: usize, col: usize) -> bool {
+        self.givens.get(row, col) != 0
+    }
+
+    // The original puzzle as it was loaded
+    pub fn get_givens(&self) -> &Grid {
+        &self.givens
+    }
+
+    // Makes the currently filled cells the givens of the puzzle
+    pub fn mark_givens(&mut self) {
+        self.givens = self.backend.to_grid();
+    }
+
+    // Removes every placed digit and restores the original puzzle, this can be undone
+    pub fn reset_to_givens(&mut self) {
+        for i in 0..81 {
+            let (row, col) = (i / 9, i % 9);
+            let given = self.givens.get(row, col);
+            if self.get(row, col) != given {
+                self.record(row, col, given);
+                self.backend.set(row, col, given);
+            }
+        }
+    }
+
+    // ---------- History ----------
+    // Once enabled every set / unset is recorded and can be undone and redone
+
+    pub fn enable_history(&mut self) {
+        if self.history.is_none() {
+            self.history = Some(History::new());
+        }
+    }
+
+    pub fn disable_history(&mut self) {
+        self.history = None;
+    }
+
+    // Copy of the grid for solvers, which would otherwise copy and extend the journal on every guess
+    pub(crate) fn clone_without_history(&self) -> Self {
+        Self {
+            backend: self.backend.clone_box(),
+            givens: self.givens,
+            givens_locked: self.givens_locked,
+            history: None,
+        }
+    }
+
+    pub fn history(&self) -> Option<&History> {
+        self.history.as_ref()
+    }
+
+    pub fn undo(&mut self) -> bool {
+        match self.history.as_mut().and_then(History::step_back) {
+            Some(mv) => {
+                self.backend.set(mv.row, mv.col, mv.old);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn redo(&mut self) -> bool {
+        match self.history.as_mut().and_then(History::step_forward) {
+            Some(mv) => {
+                self.backend.set(mv.row, mv.col, mv.new);
+                true
+            }
+            None => false,
+        }
+    }
+
+    // Undoes or redoes moves until the history is at the given position
+    pub fn jump_to(&mut self, position: usize) -> bool {
+        let Some(history) = &self.history else {
+            return false;
+        };
+        if position > history.moves().len() {
+            return false;
+        }
+        while self
+            .history
+            .as_ref()
+            .is_some_and(|history| history.position() > position)
+        {
+            self.undo();
+        }
+        while self
+            .history
+            .as_ref()
+            .is_some_and(|history| history.position() < position)
+        {
+            self.redo();
+        }
+        true
+    }
+
+    pub fn checkpoint(&mut self, name: &str) {
+        self.enable_history();
+        if let Some(history) = &mut self.history {
+            history.add_checkpoint(name);
+        }
+    }
+
+    pub fn jump_to_checkpoint(&mut self, name: &str) -> bool {
+        match self
+            .history
+            .as_ref()
+            .and_then(|history| history.checkpoint_position(name))
+        {
+            Some(position) => self.jump_to(position),
+            None => false,
+        }
+    }
+
+    #[inline]
+    fn record(&mut self, row: usize, col: usize, new: u8) {
+        if let Some(history) = &mut self.history {
+            let old = self.backend.get(row, col);
+            if old != new {
+                history.record(Move {
+                    row,
+                    col,
+                    old,
+                    new,
+                });
+            }
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    pub(crate) fn restore_history(&mut self, history: History) {
+        self.history = Some(history);
+    }
+
+    pub(crate) fn clear_history(&mut self) {
+        if let Some(history) = &mut self.history {
+            history.clear();
+        }
+    }
+
+    pub fn lock_givens(&mut self, locked: bool) {
+        self.givens_locked = locked;
+    }
+
+    pub fn are_givens_locked(&self) -> bool {
+        self.givens_locked
+    }
+
+    #[inline]
+    fn is_locked(&self, row: usize, col: usize) -> bool {
+        self.givens_locked && self.is_given(row, col)
+    }
+
     // DONT USE THIS FUNCTION UNLESS YOU KNOW WHAT YOU ARE DOING
     // This function may lead to invalid sudoku state
     pub fn set_not_zero(&mut self, row: usize, col: usize, value: u8) {
+        self.record(row, col, value);
         self.backend.set_not_zero(row, col, value)
     }
 
     // DONT USE THIS FUNCTION UNLESS YOU KNOW WHAT YOU ARE DOING
     // This function may lead to invalid sudoku state
     pub fn set_not_zero_unckecked(&mut self, row: usize, col: usize, value: u8) {
+        self.record(row, col, value);
         self.backend.set_not_zero_unckecked(row, col, value)
     }
 
@@ -61,6 +293,10 @@ impl Sudoku {
         self.backend.is_valid()
     }
 
+    pub fn get_conflicts(&self) -> Vec<Conflict> {
+        self.backend.get_conflicts()
+    }
+
     pub fn is_solved(&self) -> bool {
         self.backend.get_empty_cells().is_empty() && self.is_valid()
     }
@@ -69,6 +305,68 @@ impl Sudoku {
         solver.solve(self)
     }
 
+    pub fn solve_with_candidates(&mut self, solver: &mut dyn Solver, candidates: &Candidates) -> Option<String> {
+        solver.solve_with_candidates(self, candidates)
+    }
+
+    pub fn candidates(&self) -> Candidates {
+        Candidates::from_sudoku(self)
+    }
+
+    // Replaces the stored candidates of every empty cell, placed digits and givens are left alone.
+    // A cell down to one candidate stays empty, load Candidates::to_grid first to place those.
+    pub fn load_candidates(&mut self, candidates: &Candidates) {
+        self.backend.reset_candidates();
+        for i in 0..81 {
+            if self.get(i / 9, i % 9) == 0 {
+                self.backend.set_candidates(i / 9, i % 9, candidates.get(i / 9, i % 9));
+            }
+        }
+    }
+
+    pub fn get_candidates(&self, row: usize, col: usize) -> u16 {
+        self.backend.get_candidates(row, col)
+    }
+
+    pub fn set_candidates(&mut self, row: usize, col: usize, candidates: u16) {
+        self.backend.set_candidates(row, col, candidates)
+    }
+
+    pub fn eliminate_candidate(&mut self, row: usize, col: usize, value: u8) {
+        self.backend.eliminate_candidate(row, col, value)
+    }
+
+    pub fn reset_candidates(&mut self) {
+        self.backend.reset_candidates()
+    }
+
+    pub fn next_step(&self) -> Option<Step> {
+        logic::find_step(self)
+    }
+
+    pub fn rate(&self) -> Rating {
+        logic::rate(self)
+    }
+
+    pub fn hint(&self, level: HintLevel) -> Option<Hint> {
+        self.next_step().map(|step| Hint::from_step(step, level))
+    }
+
+    // Placements go through set so they are recorded in the history, eliminations only touch the
+    // stored candidates
+    pub fn apply_step(&mut self, step: &Step) {
+        if let Some((row, col, value)) = step.placement {
+            self.set(row, col, value);
+        }
+        for &(row, col, value) in &step.eliminations {
+            self.eliminate_candidate(row, col, value);
+        }
+    }
+
+    pub fn to_svg(&self) -> String {
+        SvgRenderer::new().render(self)
+    }
+
     pub fn is_possible_value(&self, row: usize, col: usize, value: u8) -> bool {
         self.backend.is_possible_value(row, col, value)
     }
@@ -78,10 +376,31 @@ impl Sudoku {
     }
 }
 
+impl From<Grid> for Sudoku {
+    fn from(grid: Grid) -> Self {
+        let mut sudoku = Self::new(BackendType::BitfieldGrid);
+        sudoku.load_grid(&grid);
+        sudoku
+    }
+}
+
+impl FromStr for Sudoku {
+    type Err = Error;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let mut sudoku = Self::new(BackendType::BitfieldGrid);
+        sudoku.load_from_text(input)?;
+        Ok(sudoku)
+    }
+}
+
 impl Clone for Sudoku {
     fn clone(&self) -> Self {
         Self {
             backend: self.backend.clone_box(),
+            givens: self.givens,
+            givens_locked: self.givens_locked,
+            history: self.history.clone(),
         }
     }
 }
@@ -94,31 +413,126 @@ const STRAIGHT_DOUBLE: &str = "║";
 const STRAIGHT_SINGLE: &str = "│";
 const EMPTY: &str = "   ";
 
-impl std::fmt::Display for Sudoku {
+const ANSI_RESET: &str = "\x1b[0m";
+const ANSI_GIVEN: &str = "\x1b[1m";
+const ANSI_FILLED: &str = "\x1b[36m";
+const ANSI_CONFLICT: &str = "\x1b[1;37;41m";
+const ANSI_CANDIDATE: &str = "\x1b[90m";
+
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DisplayOptions {
+    // Use ANSI escape codes, without them all other options only change the layout
+    pub color: bool,
+    // Cells filled in this grid are drawn as givens, every other digit as filled in.
+    // Defaults to the givens of the sudoku.
+    pub givens: Option<Grid>,
+    pub highlight_conflicts: bool,
+    // Show the candidates of empty cells in a 3x3 mini grid, every cell takes 3 lines
+    pub candidates: Option<Candidates>,
+}
+
+pub struct SudokuDisplay<'a> {
+    sudoku: &'a Sudoku,
+    options: DisplayOptions,
+}
+
+impl Sudoku {
+    pub fn display(&self, options: DisplayOptions) -> SudokuDisplay<'_> {
+        SudokuDisplay {
+            sudoku: self,
+            options,
+        }
+    }
+}
+
+impl SudokuDisplay<'_> {
+    fn conflicts(&self) -> [bool; 81] {
+        let mut conflicts = [false; 81];
+        for conflict in self.sudoku.get_conflicts() {
+            conflicts[conflict.first.0 * 9 + conflict.first.1] = true;
+            conflicts[conflict.second.0 * 9 + conflict.second.1] = true;
+        }
+        conflicts
+    }
+
+    fn push_cell(&self, result: &mut String, row: usize, col: usize, line: usize, conflicts: &[bool; 81]) {
+        let value = self.sudoku.get(row, col);
+        let (text, style) = if value != 0 {
+            let center = self.options.candidates.is_none() || line == 1;
+            let text = if center {
+                format!(" {} ", value)
+            } else {
+                EMPTY.to_string()
+            };
+            let style = if conflicts[row * 9 + col] {
+                ANSI_CONFLICT
+            } else if self
+                .options
+                .givens
+                .as_ref()
+                .unwrap_or(self.sudoku.get_givens())
+                .get(row, col)
+                != 0
+            {
+                ANSI_GIVEN
+            } else {
+                ANSI_FILLED
+            };
+            (text, style)
+        } else if let Some(candidates) = &self.options.candidates {
+            let text = (line as u8 * 3 + 1..=line as u8 * 3 + 3)
+                .map(|value| {
+                    if candidates.contains(row, col, value) {
+                        (value + b'0') as char
+                    } else {
+                        ' '
+                    }
+                })
+                .collect();
+            (text, ANSI_CANDIDATE)
+        } else {
+            (EMPTY.to_string(), ANSI_RESET)
+        };
+
+        if self.options.color {
+            result.push_str(style);
+            result.push_str(&text);
+            result.push_str(ANSI_RESET);
+        } else {
+            result.push_str(&text);
+        }
+    }
+}
+
+impl std::fmt::Display for SudokuDisplay<'_> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let mut result = String::new();
+        let conflicts = if self.options.highlight_conflicts {
+            self.conflicts()
+        } else {
+            [false; 81]
+        };
+        let lines = if self.options.candidates.is_some() { 3 } else { 1 };
 
         result.push_str(TOP_ROW);
         result.push('\n');
 
         for i in 0..9 {
-            result.push_str("║");
-            for j in 0..9 {
-                let value = self.get(i, j);
-                if value == 0 {
-                    result.push_str(EMPTY);
-                } else {
-                    result.push_str(&format!(" {} ", value));
-                }
+            for line in 0..lines {
+                result.push_str(STRAIGHT_DOUBLE);
+                for j in 0..9 {
+                    self.push_cell(&mut result, i, j, line, &conflicts);
 
-                if j == 2 || j == 5 {
-                    result.push_str(STRAIGHT_DOUBLE);
-                } else if j != 8 {
-                    result.push_str(STRAIGHT_SINGLE);
+                    if j == 2 || j == 5 {
+                        result.push_str(STRAIGHT_DOUBLE);
+                    } else if j != 8 {
+                        result.push_str(STRAIGHT_SINGLE);
+                    }
                 }
+                result.push_str(STRAIGHT_DOUBLE);
+                result.push('\n');
             }
-            result.push_str(STRAIGHT_DOUBLE);
-            result.push('\n');
 
             if i == 2 || i == 5 {
                 result.push_str(MIDDLE_ROW_DOUBLE);
@@ -134,3 +548,33 @@ impl std::fmt::Display for Sudoku {
         write!(f, "{}", result)
     }
 }
+
+impl std::fmt::Display for Sudoku {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.display(DisplayOptions::default()).fmt(f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn loading_candidates_keeps_the_givens() {
+        let mut grid = Grid::new();
+        grid.set(0, 0, 4);
+        let mut sudoku = Sudoku::from(grid);
+        sudoku.lock_givens(true);
+
+        let mut candidates = sudoku.candidates();
+        candidates.set(0, 1, 1 << 2);
+        candidates.set(0, 2, 1 << 2 | 1 << 5);
+        sudoku.load_candidates(&candidates);
+
+        assert_eq!(sudoku.get(0, 1), 0);
+        assert_eq!(sudoku.get_givens().get(0, 1), 0);
+        assert_eq!(sudoku.get_candidates(0, 1), 1 << 2);
+        assert_eq!(sudoku.get_candidates(0, 2), 1 << 2 | 1 << 5);
+        assert!(sudoku.try_set(0, 1, 3).is_ok());
+    }
+}
@@ -0,0 +1,343 @@
+use crate::candidates::Candidates;
+use crate::grid::Grid;
+use crate::variants::{Cage, Variants};
+use crate::Sudoku;
+use std::fmt::Write;
+
+// Renders a sudoku to a standalone SVG document, all decorations are optional
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SvgRenderer {
+    pub cell_size: f64,
+    pub margin: f64,
+    // Cells filled in the given grid are drawn as givens, every other digit as solved.
+    // Defaults to the givens of the rendered sudoku, render_grid draws all digits as givens.
+    pub givens: Option<Grid>,
+    // Pencil marks drawn into empty cells
+    pub candidates: Option<Candidates>,
+    pub highlights: Vec<(usize, usize, String)>,
+    pub cages: Vec<Cage>,
+    pub diagonals: bool,
+    // Region index of every cell for jigsaw sudokus, replaces the box borders
+    pub regions: Option<[[u8; 9]; 9]>,
+    pub given_color: String,
+    pub solved_color: String,
+    pub candidate_color: String,
+    pub line_color: String,
+    pub font_family: String,
+}
+
+impl SvgRenderer {
+    pub fn new() -> Self {
+        Self {
+            cell_size: 50.0,
+            margin: 10.0,
+            givens: None,
+            candidates: None,
+            highlights: Vec::new(),
+            cages: Vec::new(),
+            diagonals: false,
+            regions: None,
+            given_color: "#000000".to_string(),
+            solved_color: "#1f5fbf".to_string(),
+            candidate_color: "#666666".to_string(),
+            line_color: "#000000".to_string(),
+            font_family: "sans-serif".to_string(),
+        }
+    }
+
+    // The rules behind the drawn cages, diagonals and regions, e.g. for Cnf::from_grid_with
+    pub fn variants(&self) -> Variants {
+        Variants {
+            cages: self.cages.clone(),
+            diagonals: self.diagonals,
+            regions: self.regions,
+        }
+    }
+
+    pub fn render(&self, sudoku: &Sudoku) -> String {
+        self.render_with_givens(&sudoku.to_grid(), self.givens.as_ref().or(Some(sudoku.get_givens())))
+    }
+
+    pub fn render_grid(&self, grid: &Grid) -> String {
+        self.render_with_givens(grid, self.givens.as_ref())
+    }
+
+    fn render_with_givens(&self, grid: &Grid, givens: Option<&Grid>) -> String {
+        let size = self.cell_size * 9.0 + self.margin * 2.0;
+        let mut svg = String::new();
+
+        let _ = writeln!(
+            svg,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{size}" height="{size}" viewBox="0 0 {size} {size}">"#
+        );
+        let _ = writeln!(svg, r##"<rect width="{size}" height="{size}" fill="#ffffff"/>"##);
+
+        for (row, col, color) in &self.highlights {
+            let (x, y) = self.cell_origin(*row, *col);
+            let _ = writeln!(
+                svg,
+                r#"<rect x="{x}" y="{y}" width="{s}" height="{s}" fill="{}"/>"#,
+                escape(color),
+                s = self.cell_size
+            );
+        }
+
+        if self.diagonals {
+            self.render_diagonals(&mut svg);
+        }
+        self.render_lines(&mut svg);
+        for cage in &self.cages {
+            self.render_cage(&mut svg, cage);
+        }
+        self.render_digits(&mut svg, grid, givens);
+
+        svg.push_str("</svg>\n");
+        svg
+    }
+
+    fn cell_origin(&self, row: usize, col: usize) -> (f64, f64) {
+        (
+            self.margin + col as f64 * self.cell_size,
+            self.margin + row as f64 * self.cell_size,
+        )
+    }
+
+    fn region(&self, row: usize, col: usize) -> u8 {
+        match &self.regions {
+            Some(regions) => regions[row][col],
+            None => (row / 3 * 3 + col / 3) as u8,
+        }
+    }
+
+    fn line(&self, svg: &mut String, from: (f64, f64), to: (f64, f64), width: f64) {
+        let _ = writeln!(
+            svg,
+            r#"<line x1="{}" y1="{}" x2="{}" y2="{}" stroke="{}" stroke-width="{}" stroke-linecap="square"/>"#,
+            from.0,
+            from.1,
+            to.0,
+            to.1,
+            escape(&self.line_color),
+            width
+        );
+    }
+
+    // Thin lines between all cells, thick lines between regions and around the grid
+    fn render_lines(&self, svg: &mut String) {
+        let thin = self.cell_size / 50.0;
+        let thick = thin * 3.0;
+
+        for row in 0..9 {
+            for col in 0..9 {
+                let (x, y) = self.cell_origin(row, col);
+                if col < 8 {
+                    let width = if self.region(row, col) == self.region(row, col + 1) {
+                        thin
+                    } else {
+                        thick
+                    };
+                    self.line(
+                        svg,
+                        (x + self.cell_size, y),
+                        (x + self.cell_size, y + self.cell_size),
+                        width,
+                    );
+                }
+                if row < 8 {
+                    let width = if self.region(row, col) == self.region(row + 1, col) {
+                        thin
+                    } else {
+                        thick
+                    };
+                    self.line(
+                        svg,
+                        (x, y + self.cell_size),
+                        (x + self.cell_size, y + self.cell_size),
+                        width,
+                    );
+                }
+            }
+        }
+
+        let size = self.cell_size * 9.0;
+        let _ = writeln!(
+            svg,
+            r#"<rect x="{m}" y="{m}" width="{size}" height="{size}" fill="none" stroke="{}" stroke-width="{thick}"/>"#,
+            escape(&self.line_color),
+            m = self.margin
+        );
+    }
+
+    fn render_diagonals(&self, svg: &mut String) {
+        let start = self.margin;
+        let end = self.margin + self.cell_size * 9.0;
+        for (from, to) in [((start, start), (end, end)), ((start, end), (end, start))] {
+            let _ = writeln!(
+                svg,
+                r##"<line x1="{}" y1="{}" x2="{}" y2="{}" stroke="#999999" stroke-width="{}"/>"##,
+                from.0,
+                from.1,
+                to.0,
+                to.1,
+                self.cell_size / 25.0
+            );
+        }
+    }
+
+    // Cages are drawn as dashed outlines slightly inset from the cell borders
+    fn render_cage(&self, svg: &mut String, cage: &Cage) {
+        let inset = self.cell_size * 0.08;
+        let width = self.cell_size / 50.0;
+        let contains = |row: isize, col: isize| cage.cells.iter().any(|&(r, c)| r as isize == row && c as isize == col);
+
+        for &(row, col) in &cage.cells {
+            let (x, y) = self.cell_origin(row, col);
+            let (r, c) = (row as isize, col as isize);
+            let (left, right) = (x + inset, x + self.cell_size - inset);
+            let (top, bottom) = (y + inset, y + self.cell_size - inset);
+            // extend edges into neighbouring cage cells so the outline also closes at inner corners
+            let x0 = if contains(r, c - 1) { x - inset } else { left };
+            let x1 = if contains(r, c + 1) {
+                x + self.cell_size + inset
+            } else {
+                right
+            };
+            let y0 = if contains(r - 1, c) { y - inset } else { top };
+            let y1 = if contains(r + 1, c) {
+                y + self.cell_size + inset
+            } else {
+                bottom
+            };
+
+            let mut edges = Vec::new();
+            if !contains(r - 1, c) {
+                edges.push(((x0, top), (x1, top)));
+            }
+            if !contains(r + 1, c) {
+                edges.push(((x0, bottom), (x1, bottom)));
+            }
+            if !contains(r, c - 1) {
+                edges.push(((left, y0), (left, y1)));
+            }
+            if !contains(r, c + 1) {
+                edges.push(((right, y0), (right, y1)));
+            }
+            for (from, to) in edges {
+                let _ = writeln!(
+                    svg,
+                    r#"<line x1="{}" y1="{}" x2="{}" y2="{}" stroke="{}" stroke-width="{width}" stroke-dasharray="{dash} {dash}"/>"#,
+                    from.0,
+                    from.1,
+                    to.0,
+                    to.1,
+                    escape(&self.line_color),
+                    dash = self.cell_size / 12.5
+                );
+            }
+        }
+
+        // the sum is written into the top left cell of the cage
+        if let (Some(sum), Some(&(row, col))) = (cage.sum, cage.cells.iter().min()) {
+            let (x, y) = self.cell_origin(row, col);
+            let font_size = self.cell_size * 0.22;
+            let _ = writeln!(
+                svg,
+                r#"<text x="{}" y="{}" font-family="{}" font-size="{font_size}" fill="{}">{sum}</text>"#,
+                x + inset * 1.2,
+                y + inset + font_size,
+                escape(&self.font_family),
+                escape(&self.line_color)
+            );
+        }
+    }
+
+    fn render_digits(&self, svg: &mut String, grid: &Grid, givens: Option<&Grid>) {
+        for row in 0..9 {
+            for col in 0..9 {
+                let (x, y) = self.cell_origin(row, col);
+                let value = grid.get(row, col);
+
+                if value != 0 {
+                    let given = givens.is_none_or(|givens| givens.get(row, col) != 0);
+                    let (color, weight) = if given {
+                        (escape(&self.given_color), "bold")
+                    } else {
+                        (escape(&self.solved_color), "normal")
+                    };
+                    let _ = writeln!(
+                        svg,
+                        r#"<text x="{}" y="{}" font-family="{}" font-size="{}" font-weight="{weight}" fill="{color}" text-anchor="middle" dominant-baseline="central">{value}</text>"#,
+                        x + self.cell_size / 2.0,
+                        y + self.cell_size / 2.0,
+                        escape(&self.font_family),
+                        self.cell_size * 0.6
+                    );
+                    continue;
+                }
+
+                let Some(candidates) = &self.candidates else {
+                    continue;
+                };
+                let third = self.cell_size / 3.0;
+                for candidate in 1..=9u8 {
+                    if !candidates.contains(row, col, candidate) {
+                        continue;
+                    }
+                    let idx = (candidate - 1) as f64;
+                    let _ = writeln!(
+                        svg,
+                        r#"<text x="{}" y="{}" font-family="{}" font-size="{}" fill="{}" text-anchor="middle" dominant-baseline="central">{candidate}</text>"#,
+                        x + third * (idx % 3.0) + third / 2.0,
+                        y + third * (idx / 3.0).floor() + third / 2.0,
+                        escape(&self.font_family),
+                        self.cell_size * 0.22,
+                        escape(&self.candidate_color)
+                    );
+                }
+            }
+        }
+    }
+}
+
+impl Default for SvgRenderer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// User provided strings end up in double quoted attributes
+fn escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for chr in value.chars() {
+        match chr {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            _ => escaped.push(chr),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn user_strings_are_escaped() {
+        let mut renderer = SvgRenderer::new();
+        renderer.line_color = r#"red"/><script>"#.to_string();
+        renderer.font_family = "'A&B'".to_string();
+        renderer.highlights.push((0, 0, "<x>".to_string()));
+        let mut grid = Grid::new();
+        grid.set(4, 4, 5);
+        let svg = renderer.render_grid(&grid);
+        assert!(!svg.contains("<script>"));
+        assert!(svg.contains(r#"stroke="red&quot;/&gt;&lt;script&gt;""#));
+        assert!(svg.contains("fill=\"&lt;x&gt;\""));
+        assert!(svg.contains(r#"font-family="&apos;A&amp;B&apos;""#));
+    }
+}
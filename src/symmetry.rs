@@ -0,0 +1,155 @@
+use crate::canonical::canonical_transforms;
+use crate::error::Error;
+use crate::grid::Grid;
+use crate::transform::Transform;
+use std::fmt;
+
+const REVERSED: [usize; 9] = [8, 7, 6, 5, 4, 3, 2, 1, 0];
+const IDENTITY: [usize; 9] = [0, 1, 2, 3, 4, 5, 6, 7, 8];
+
+// The geometric symmetries of the square besides the identity
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Symmetry {
+    Rotate90,
+    Rotate180,
+    Rotate270,
+    // top and bottom swapped
+    MirrorHorizontal,
+    // left and right swapped
+    MirrorVertical,
+    Diagonal,
+    AntiDiagonal,
+}
+
+impl Symmetry {
+    pub const ALL: [Symmetry; 7] = [
+        Symmetry::Rotate90,
+        Symmetry::Rotate180,
+        Symmetry::Rotate270,
+        Symmetry::MirrorHorizontal,
+        Symmetry::MirrorVertical,
+        Symmetry::Diagonal,
+        Symmetry::AntiDiagonal,
+    ];
+
+    // Where the cell ends up under this symmetry
+    pub fn map(&self, row: usize, col: usize) -> (usize, usize) {
+        match self {
+            Symmetry::Rotate90 => (col, 8 - row),
+            Symmetry::Rotate180 => (8 - row, 8 - col),
+            Symmetry::Rotate270 => (8 - col, row),
+            Symmetry::MirrorHorizontal => (8 - row, col),
+            Symmetry::MirrorVertical => (row, 8 - col),
+            Symmetry::Diagonal => (col, row),
+            Symmetry::AntiDiagonal => (8 - col, 8 - row),
+        }
+    }
+
+    // The cells the given one is mapped to by repeating the symmetry, including itself
+    pub fn orbit(&self, row: usize, col: usize) -> Vec<(usize, usize)> {
+        let mut orbit = vec![(row, col)];
+        let mut cell = self.map(row, col);
+        while cell != (row, col) {
+            orbit.push(cell);
+            cell = self.map(cell.0, cell.1);
+        }
+        orbit
+    }
+
+    pub fn transform(&self) -> Transform {
+        let digits = [1, 2, 3, 4, 5, 6, 7, 8, 9];
+        let (transpose, rows, cols) = match self {
+            Symmetry::Rotate90 => (true, IDENTITY, REVERSED),
+            Symmetry::Rotate180 => (false, REVERSED, REVERSED),
+            Symmetry::Rotate270 => (true, REVERSED, IDENTITY),
+            Symmetry::MirrorHorizontal => (false, REVERSED, IDENTITY),
+            Symmetry::MirrorVertical => (false, IDENTITY, REVERSED),
+            Symmetry::Diagonal => (true, IDENTITY, IDENTITY),
+            Symmetry::AntiDiagonal => (true, REVERSED, REVERSED),
+        };
+        Transform::from_parts(transpose, rows, cols, digits)
+    }
+}
+
+impl fmt::Display for Symmetry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Symmetry::Rotate90 => "90° rotation",
+            Symmetry::Rotate180 => "180° rotation",
+            Symmetry::Rotate270 => "270° rotation",
+            Symmetry::MirrorHorizontal => "horizontal mirror",
+            Symmetry::MirrorVertical => "vertical mirror",
+            Symmetry::Diagonal => "diagonal",
+            Symmetry::AntiDiagonal => "anti-diagonal",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl Grid {
+    // Every transform that maps the grid onto itself, including the identity. Digits that do
+    // not appear in the grid always keep their label.
+    pub fn automorphisms(&self) -> Result<Vec<Transform>, Error> {
+        let (_, transforms) = canonical_transforms(self)?;
+        let back = transforms[0].inverse();
+        Ok(transforms.iter().map(|transform| transform.then(&back)).collect())
+    }
+
+    // The geometric symmetries that map the grid onto itself once its digits are relabeled,
+    // with the relabeling (digit d becomes digits[d - 1])
+    pub fn symmetries(&self) -> Vec<(Symmetry, [u8; 9])> {
+        Symmetry::ALL
+            .iter()
+            .filter_map(|&symmetry| {
+                let moved = symmetry.transform().apply(self);
+                relabeling(&moved, self).map(|digits| (symmetry, digits))
+            })
+            .collect()
+    }
+
+    // The geometric symmetries of the clue layout alone, ignoring the digits
+    pub fn clue_symmetries(&self) -> Vec<Symmetry> {
+        let clues = Grid::from_cells(self.cells().map(|value| (value != 0) as u8));
+        Symmetry::ALL
+            .iter()
+            .copied()
+            .filter(|symmetry| symmetry.transform().apply(&clues) == clues)
+            .collect()
+    }
+
+    pub fn has_symmetric_clues(&self) -> bool {
+        !self.clue_symmetries().is_empty()
+    }
+}
+
+// The digit permutation turning from into to, if there is one
+fn relabeling(from: &Grid, to: &Grid) -> Option<[u8; 9]> {
+    let mut digits = [0u8; 9];
+    let mut used = [false; 9];
+    for (&a, &b) in from.cells().iter().zip(to.cells()) {
+        if (a == 0) != (b == 0) {
+            return None;
+        }
+        if a == 0 {
+            continue;
+        }
+        let digit = &mut digits[a as usize - 1];
+        if *digit == 0 {
+            if used[b as usize - 1] {
+                return None;
+            }
+            *digit = b;
+            used[b as usize - 1] = true;
+        } else if *digit != b {
+            return None;
+        }
+    }
+
+    // digits missing from the grid map onto the remaining labels in order
+    let mut free = (1..=9).filter(|&digit| !used[digit as usize - 1]);
+    for digit in digits.iter_mut().filter(|digit| **digit == 0) {
+        *digit = free.next()?;
+    }
+    Some(digits)
+}
@@ -0,0 +1,234 @@
+use crate::grid::Grid;
+use crate::rng::Rng;
+use crate::Sudoku;
+
+const IDENTITY: [usize; 9] = [0, 1, 2, 3, 4, 5, 6, 7, 8];
+
+// A validity preserving transformation of a grid. The grid is transposed first if requested,
+// then cell (row, col) of the result is taken from (rows[row], cols[col]) and its digit is
+// relabeled. Rows and columns only move within their band / stack or together with it, so every
+// value of this type maps solutions to solutions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Transform {
+    transpose: bool,
+    rows: [usize; 9],
+    cols: [usize; 9],
+    // digit d becomes digits[d - 1]
+    digits: [u8; 9],
+}
+
+impl Transform {
+    pub fn identity() -> Self {
+        Self {
+            transpose: false,
+            rows: IDENTITY,
+            cols: IDENTITY,
+            digits: [1, 2, 3, 4, 5, 6, 7, 8, 9],
+        }
+    }
+
+    // The caller guarantees that rows and cols keep lines inside their bands and stacks
+    pub(crate) fn from_parts(transpose: bool, rows: [usize; 9], cols: [usize; 9], digits: [u8; 9]) -> Self {
+        Self {
+            transpose,
+            rows,
+            cols,
+            digits,
+        }
+    }
+
+    // None if digits is not a permutation of 1..=9
+    pub fn relabel(digits: [u8; 9]) -> Option<Self> {
+        let mut sorted = digits;
+        sorted.sort_unstable();
+        if sorted != [1, 2, 3, 4, 5, 6, 7, 8, 9] {
+            return None;
+        }
+        Some(Self {
+            digits,
+            ..Self::identity()
+        })
+    }
+
+    // Swaps rows a and b (0..3) inside the band (0..3)
+    pub fn swap_rows(band: usize, a: usize, b: usize) -> Self {
+        assert!(band < 3 && a < 3 && b < 3, "band and offsets must be below 3");
+        let mut transform = Self::identity();
+        transform.rows.swap(band * 3 + a, band * 3 + b);
+        transform
+    }
+
+    pub fn swap_cols(stack: usize, a: usize, b: usize) -> Self {
+        assert!(stack < 3 && a < 3 && b < 3, "stack and offsets must be below 3");
+        let mut transform = Self::identity();
+        transform.cols.swap(stack * 3 + a, stack * 3 + b);
+        transform
+    }
+
+    pub fn swap_bands(a: usize, b: usize) -> Self {
+        assert!(a < 3 && b < 3, "there are only 3 bands and stacks");
+        let mut transform = Self::identity();
+        for i in 0..3 {
+            transform.rows.swap(a * 3 + i, b * 3 + i);
+        }
+        transform
+    }
+
+    pub fn swap_stacks(a: usize, b: usize) -> Self {
+        assert!(a < 3 && b < 3, "there are only 3 bands and stacks");
+        let mut transform = Self::identity();
+        for i in 0..3 {
+            transform.cols.swap(a * 3 + i, b * 3 + i);
+        }
+        transform
+    }
+
+    pub fn transpose() -> Self {
+        Self {
+            transpose: true,
+            ..Self::identity()
+        }
+    }
+
+    // Quarter turn clockwise
+    pub fn rotate() -> Self {
+        Self {
+            transpose: true,
+            cols: [8, 7, 6, 5, 4, 3, 2, 1, 0],
+            ..Self::identity()
+        }
+    }
+
+    pub fn is_transposed(&self) -> bool {
+        self.transpose
+    }
+
+    // Source row of every row of the result (after the transposition)
+    pub fn rows(&self) -> &[usize; 9] {
+        &self.rows
+    }
+
+    pub fn cols(&self) -> &[usize; 9] {
+        &self.cols
+    }
+
+    // Digit d becomes digits()[d - 1]
+    pub fn digits(&self) -> &[u8; 9] {
+        &self.digits
+    }
+
+    // Uniformly random element of the transformation group, the same seed gives the same result
+    pub fn random(seed: u64) -> Self {
+        Self::random_with(&mut Rng::new(seed))
+    }
+
+    pub(crate) fn random_with(rng: &mut Rng) -> Self {
+        let mut transform = Self {
+            transpose: rng.below(2) == 1,
+            ..Self::identity()
+        };
+        for lines in [&mut transform.rows, &mut transform.cols] {
+            let mut groups = [0, 1, 2];
+            rng.shuffle(&mut groups);
+            for (i, group) in groups.into_iter().enumerate() {
+                let mut offsets = [0, 1, 2];
+                rng.shuffle(&mut offsets);
+                for (j, offset) in offsets.into_iter().enumerate() {
+                    lines[i * 3 + j] = group * 3 + offset;
+                }
+            }
+        }
+        rng.shuffle(&mut transform.digits);
+        transform
+    }
+
+    // Applies self first and other second
+    pub fn then(&self, other: &Transform) -> Self {
+        let (rows, cols) = if other.transpose {
+            (
+                other.rows.map(|row| self.cols[row]),
+                other.cols.map(|col| self.rows[col]),
+            )
+        } else {
+            (
+                other.rows.map(|row| self.rows[row]),
+                other.cols.map(|col| self.cols[col]),
+            )
+        };
+        Self {
+            transpose: self.transpose != other.transpose,
+            rows,
+            cols,
+            digits: self.digits.map(|digit| other.digits[digit as usize - 1]),
+        }
+    }
+
+    pub fn inverse(&self) -> Self {
+        let mut rows = [0; 9];
+        let mut cols = [0; 9];
+        let mut digits = [0; 9];
+        for i in 0..9 {
+            rows[self.rows[i]] = i;
+            cols[self.cols[i]] = i;
+            digits[self.digits[i] as usize - 1] = i as u8 + 1;
+        }
+        if self.transpose {
+            (rows, cols) = (cols, rows);
+        }
+        Self {
+            transpose: self.transpose,
+            rows,
+            cols,
+            digits,
+        }
+    }
+
+    pub fn apply(&self, grid: &Grid) -> Grid {
+        let mut result = Grid::new();
+        for row in 0..9 {
+            for col in 0..9 {
+                let (from_row, from_col) = if self.transpose {
+                    (self.cols[col], self.rows[row])
+                } else {
+                    (self.rows[row], self.cols[col])
+                };
+                let value = grid.get(from_row, from_col);
+                if value != 0 {
+                    result.set(row, col, self.digits[value as usize - 1]);
+                }
+            }
+        }
+        result
+    }
+}
+
+impl Default for Transform {
+    fn default() -> Self {
+        Self::identity()
+    }
+}
+
+impl Grid {
+    pub fn transform(&self, transform: &Transform) -> Grid {
+        transform.apply(self)
+    }
+}
+
+impl Sudoku {
+    // Transforms the givens and the placed digits alike, the history and candidates are reset
+    pub fn transform(&mut self, transform: &Transform) {
+        let cells = transform.apply(&self.to_grid());
+        let givens = transform.apply(self.get_givens());
+        let locked = self.are_givens_locked();
+
+        self.lock_givens(false);
+        self.load_grid(&givens);
+        for (i, &value) in cells.cells().iter().enumerate() {
+            if value != 0 && givens.cells()[i] == 0 {
+                self.set(i / 9, i % 9, value);
+            }
+        }
+        self.clear_history();
+        self.lock_givens(locked);
+    }
+}
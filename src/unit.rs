@@ -0,0 +1,59 @@
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Unit {
+    Row(usize),
+    Col(usize),
+    Box(usize),
+}
+
+impl Unit {
+    pub fn all() -> impl Iterator<Item = Unit> {
+        (0..9)
+            .map(Unit::Row)
+            .chain((0..9).map(Unit::Col))
+            .chain((0..9).map(Unit::Box))
+    }
+
+    // The (row, col) coordinates of the 9 cells in this unit
+    pub fn cells(&self) -> [(usize, usize); 9] {
+        let mut cells = [(0, 0); 9];
+        for (i, cell) in cells.iter_mut().enumerate() {
+            *cell = match *self {
+                Unit::Row(row) => (row, i),
+                Unit::Col(col) => (i, col),
+                Unit::Box(box_idx) => (box_idx / 3 * 3 + i / 3, box_idx % 3 * 3 + i % 3),
+            };
+        }
+        cells
+    }
+
+    pub fn contains(&self, row: usize, col: usize) -> bool {
+        match *self {
+            Unit::Row(r) => r == row,
+            Unit::Col(c) => c == col,
+            Unit::Box(box_idx) => box_idx == row / 3 * 3 + col / 3,
+        }
+    }
+}
+
+impl fmt::Display for Unit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Unit::Row(row) => write!(f, "row {}", row + 1),
+            Unit::Col(col) => write!(f, "column {}", col + 1),
+            Unit::Box(box_idx) => write!(f, "box {}", box_idx + 1),
+        }
+    }
+}
+
+// Two cells of the same unit holding the same value
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Conflict {
+    pub first: (usize, usize),
+    pub second: (usize, usize),
+    pub unit: Unit,
+    pub value: u8,
+}
@@ -0,0 +1,78 @@
+use crate::error::Error;
+use crate::solutions::UNITS;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Cage {
+    pub cells: Vec<(usize, usize)>,
+    pub sum: Option<u32>,
+}
+
+// Rules on top of classic sudoku: killer cages, the two main diagonals and jigsaw regions
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Variants {
+    // digits in a cage are distinct and add up to its sum if it has one
+    pub cages: Vec<Cage>,
+    // both main diagonals hold every digit once
+    pub diagonals: bool,
+    // Region index of every cell for jigsaw sudokus, replaces the boxes
+    pub regions: Option<[[u8; 9]; 9]>,
+}
+
+impl Variants {
+    pub fn is_classic(&self) -> bool {
+        self.cages.is_empty() && !self.diagonals && self.regions.is_none()
+    }
+
+    // Cell indices of every group of 9 cells that holds each digit exactly once
+    pub fn units(&self) -> Result<Vec<[usize; 9]>, Error> {
+        let mut units = UNITS[..18].to_vec();
+        match &self.regions {
+            Some(regions) => {
+                let mut sizes = [0; 9];
+                let mut cells = [[0; 9]; 9];
+                for (cell, &region) in regions.iter().flatten().enumerate() {
+                    let size = sizes
+                        .get_mut(region as usize)
+                        .ok_or(Error::InvalidVariant("region index above 8"))?;
+                    if *size == 9 {
+                        return Err(Error::InvalidVariant("region with more than 9 cells"));
+                    }
+                    cells[region as usize][*size] = cell;
+                    *size += 1;
+                }
+                units.extend(cells);
+            }
+            None => units.extend_from_slice(&UNITS[18..]),
+        }
+        if self.diagonals {
+            units.push(std::array::from_fn(|i| i * 10));
+            units.push(std::array::from_fn(|i| i * 8 + 8));
+        }
+        Ok(units)
+    }
+
+    // Cell indices of every cage, checked to be distinct cells of the grid that fit 9 digits
+    pub fn cage_cells(&self) -> Result<Vec<Vec<usize>>, Error> {
+        self.cages
+            .iter()
+            .map(|cage| {
+                if cage.cells.is_empty() || cage.cells.len() > 9 {
+                    return Err(Error::InvalidVariant("cage must have 1 to 9 cells"));
+                }
+                let mut cells = Vec::with_capacity(cage.cells.len());
+                for &(row, col) in &cage.cells {
+                    if row > 8 || col > 8 {
+                        return Err(Error::InvalidVariant("cage cell outside the grid"));
+                    }
+                    if cells.contains(&(row * 9 + col)) {
+                        return Err(Error::InvalidVariant("cage contains a cell twice"));
+                    }
+                    cells.push(row * 9 + col);
+                }
+                Ok(cells)
+            })
+            .collect()
+    }
+}